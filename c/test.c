@@ -0,0 +1,112 @@
+/* Exercises the C API: run `make -C c test` after `cargo build --release`. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "dnd_solver.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                   \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,    \
+                    __LINE__, #cond);                                 \
+            failures++;                                               \
+        }                                                             \
+    } while (0)
+
+/* Last Call BBS 5-8 */
+static const char *PUZZLE =
+    " 35344253\n"
+    "4M   M M \n"
+    "4        \n"
+    "2M       \n"
+    "4       M\n"
+    "6M       \n"
+    "2       M\n"
+    "3        \n"
+    "4 M   M M\n";
+
+static const char *SOLUTION =
+    " 35344253\n"
+    "4MxxxM,Mx\n"
+    "4,,,xx,xx\n"
+    "2Mx,,x,,,\n"
+    "4xxx,,,xM\n"
+    "6Mxx,xxxx\n"
+    "2,,,,,xxM\n"
+    "3xx,x,,,,\n"
+    "4xM,xxMxM\n";
+
+int main(void) {
+    printf("dnd_solver %s\n", dnd_version());
+
+    DndBoard *board = dnd_board_parse(PUZZLE);
+    CHECK(board != NULL);
+    CHECK(dnd_board_check(board) == DND_STATUS_NOT_SOLVED);
+    CHECK(strcmp(dnd_last_error(), "Unsolved") == 0);
+    CHECK(dnd_last_status() == DND_STATUS_NOT_SOLVED);
+
+    DndBoardError errors[4];
+    size_t count = 0;
+    CHECK(dnd_board_violations(board, errors, 4, &count) == DND_STATUS_OK);
+    CHECK(count == 1 && errors[0].kind == DND_ERROR_KIND_UNSOLVED);
+    CHECK(dnd_board_violations(board, NULL, 0, &count) == DND_STATUS_OK && count == 1);
+
+    DndCell cell;
+    CHECK(dnd_board_cell(board, 0, 0, &cell) == DND_STATUS_OK && cell == DND_CELL_MONSTER);
+    CHECK(dnd_board_cell(board, 0, 1, &cell) == DND_STATUS_OK && cell == DND_CELL_UNKNOWN);
+    CHECK(dnd_board_cell(board, 8, 0, &cell) == DND_STATUS_OUT_OF_BOUNDS);
+
+    /* A tiny budget runs out and leaves the board alone */
+    DndSolveOptions options = dnd_solve_options_default();
+    options.node_budget = 1;
+    CHECK(dnd_board_solve(board, &options) == DND_STATUS_BUDGET_EXCEEDED);
+    CHECK(dnd_board_cell(board, 0, 1, &cell) == DND_STATUS_OK && cell == DND_CELL_UNKNOWN);
+
+    options = dnd_solve_options_default();
+    options.variable_order = 4;
+    CHECK(dnd_board_solve(board, &options) == DND_STATUS_INVALID_OPTION);
+
+    DndBoard *copy = dnd_board_clone(board);
+    options.variable_order = 1;
+    CHECK(dnd_board_solve(copy, &options) == DND_STATUS_OK);
+    CHECK(dnd_board_solve(board, NULL) == DND_STATUS_OK);
+    CHECK(dnd_board_check(board) == DND_STATUS_OK);
+
+    char *text = dnd_board_to_string(board);
+    CHECK(text != NULL && strcmp(text, SOLUTION) == 0);
+    dnd_string_free(text);
+    text = dnd_board_to_string(copy);
+    CHECK(text != NULL && strcmp(text, SOLUTION) == 0);
+    dnd_string_free(text);
+    dnd_board_free(copy);
+    dnd_board_free(board);
+
+    CHECK(dnd_board_parse("not a puzzle") == NULL);
+    CHECK(dnd_last_error() != NULL);
+    CHECK(dnd_last_status() == DND_STATUS_INVALID_PUZZLE);
+    CHECK(dnd_board_parse(NULL) == NULL);
+    board = dnd_board_from_code("AYg1NEJTRCRiNIoAgAGAAQBFAAAAhFo");
+    CHECK(board != NULL);
+    dnd_board_free(board);
+    CHECK(dnd_board_from_code("AYg1") == NULL);
+    dnd_board_free(NULL);
+
+    DndBoard *solution = NULL;
+    board = dnd_generate(1, 1, false, 0, &solution);
+    CHECK(board != NULL && solution != NULL);
+    CHECK(dnd_board_check(solution) == DND_STATUS_OK);
+    dnd_board_free(solution);
+    dnd_board_free(board);
+    CHECK(dnd_generate(1, 1, false, 10, NULL) == NULL);
+    CHECK(dnd_last_status() == DND_STATUS_BUDGET_EXCEEDED);
+
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return 1;
+    }
+    printf("all checks passed\n");
+    return 0;
+}
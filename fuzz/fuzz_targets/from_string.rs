@@ -0,0 +1,17 @@
+//! `Board::from_string` must never panic, and whatever it accepts must print back to
+//! text that parses to the same board.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zach_dnd_solver::Board;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(spec) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(board) = Board::from_string(spec) {
+        let printed = board.to_string();
+        assert_eq!(Board::from_string(&printed).ok(), Some(board), "{printed}");
+    }
+});
@@ -0,0 +1,17 @@
+//! Puzzle files must never make the parser panic, and what it accepts must survive a
+//! round trip through `write_puzzles`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zach_dnd_solver::format::{parse_puzzles, write_puzzles};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(spec) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(puzzles) = parse_puzzles(spec) {
+        let written = write_puzzles(&puzzles);
+        assert_eq!(parse_puzzles(&written), Ok(puzzles), "{written}");
+    }
+});
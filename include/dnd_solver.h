@@ -0,0 +1,203 @@
+#ifndef DND_SOLVER_H
+#define DND_SOLVER_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum DndStatus {
+  DND_STATUS_OK = 0,
+  DND_STATUS_NULL_ARGUMENT,
+  DND_STATUS_INVALID_PUZZLE,
+  DND_STATUS_INVALID_OPTION,
+  DND_STATUS_OUT_OF_BOUNDS,
+  DND_STATUS_UNSOLVABLE,
+  DND_STATUS_BUDGET_EXCEEDED,
+  // The board breaks a rule or is incomplete; see `dnd_last_error` for which.
+  DND_STATUS_NOT_SOLVED,
+  // A bug in the library; see `dnd_last_error`.
+  DND_STATUS_INTERNAL,
+} DndStatus;
+
+// Which rule a board breaks, as reported by `dnd_board_check_error`.
+typedef enum DndErrorKind {
+  DND_ERROR_KIND_NONE = 0,
+  DND_ERROR_KIND_UNSOLVED,
+  DND_ERROR_KIND_WRONG_ROW_COUNT,
+  DND_ERROR_KIND_WRONG_COLUMN_COUNT,
+  DND_ERROR_KIND_MONSTER_NOT_IN_DEAD_END,
+  DND_ERROR_KIND_DEAD_END_WITH_NO_MONSTER,
+  DND_ERROR_KIND_NO_TREASURE_ROOM_FOR_CHEST,
+  DND_ERROR_KIND_CORRIDORS_TOO_WIDE,
+  DND_ERROR_KIND_UNCONNECTED_CORRIDORS,
+} DndErrorKind;
+
+typedef enum DndCell {
+  DND_CELL_UNKNOWN = 0,
+  DND_CELL_EMPTY,
+  DND_CELL_WALL,
+  DND_CELL_MONSTER,
+  DND_CELL_CHEST,
+} DndCell;
+
+// A puzzle or a (partially) solved board.
+typedef struct DndBoard DndBoard;
+
+// Options for `dnd_board_solve`; start from `dnd_solve_options_default`.
+typedef struct DndSolveOptions {
+  // 0 reading order, 1 most constrained, 2 near clues, 3 least slack.
+  uint32_t variable_order;
+  // 0 wall first, 1 empty first, 2 remaining count.
+  uint32_t value_order;
+  // Search nodes to give up after, 0 for no limit.
+  uint64_t node_budget;
+} DndSolveOptions;
+
+// A broken rule and where; `row` and `col` are -1 where they do not apply.
+typedef struct DndBoardError {
+  enum DndErrorKind kind;
+  int32_t row;
+  int32_t col;
+} DndBoardError;
+
+// The version of the library, as a static string.
+const char *dnd_version(void);
+
+// The message left by the last failed call on this thread, or null. It stays valid until
+// the next failure.
+const char *dnd_last_error(void);
+
+// The status of the last failure on this thread, or `DND_STATUS_OK` if nothing failed
+// yet. Useful after a function that returns a null pointer.
+enum DndStatus dnd_last_status(void);
+
+// Parses a puzzle in the text format, either a bare grid or a file holding one puzzle.
+//
+// # Safety
+//
+// `text` must be null or a NUL-terminated string.
+struct DndBoard *dnd_board_parse(const char *text);
+
+// Decodes a puzzle code.
+//
+// # Safety
+//
+// `code` must be null or a NUL-terminated string.
+struct DndBoard *dnd_board_from_code(const char *code);
+
+// A copy of the board, released separately.
+//
+// # Safety
+//
+// `board` must be null or a live handle.
+struct DndBoard *dnd_board_clone(const struct DndBoard *board);
+
+// Releases a board; null is ignored.
+//
+// # Safety
+//
+// `board` must be null or a live handle, which must not be used afterwards.
+void dnd_board_free(struct DndBoard *board);
+
+struct DndSolveOptions dnd_solve_options_default(void);
+
+// Replaces the board with its first solution. The board is left alone on failure.
+//
+// # Safety
+//
+// `board` must be null or a live handle, and `options` null (for the defaults) or valid.
+enum DndStatus dnd_board_solve(struct DndBoard *board, const struct DndSolveOptions *options);
+
+// Counts the solutions of the board, stopping at `limit`, and writes the count to `count`.
+//
+// # Safety
+//
+// `board` must be null or a live handle, and `count` null or valid for writes.
+enum DndStatus dnd_board_count_solutions(const struct DndBoard *board,
+                                         uint64_t limit,
+                                         uint64_t node_budget,
+                                         uint64_t *count);
+
+// Generates a puzzle with a unique solution; the same seed always gives the same puzzle.
+//
+// Gives up after `node_budget` search nodes, or never if it is 0. Unless `solution` is
+// null, it receives a handle to the solution, to be freed like the puzzle.
+//
+// # Safety
+//
+// `solution` must be null or valid for writes.
+struct DndBoard *dnd_generate(uint64_t seed,
+                              uint32_t rooms,
+                              bool hide_counts,
+                              uint64_t node_budget,
+                              struct DndBoard **solution);
+
+// Whether the board is a complete and valid solution.
+//
+// # Safety
+//
+// `board` must be null or a live handle.
+enum DndStatus dnd_board_check(const struct DndBoard *board);
+
+// Like `dnd_board_check`, but also writes the broken rule to `error`.
+//
+// # Safety
+//
+// `board` must be null or a live handle, and `error` null or valid for writes.
+enum DndStatus dnd_board_check_error(const struct DndBoard *board, struct DndBoardError *error);
+
+// Writes every rule the board breaks to `errors`, up to `capacity` of them, and their
+// total number to `count`. A board with `Unknown` cells only breaks `Unsolved`.
+//
+// # Safety
+//
+// `board` must be null or a live handle, `errors` valid for `capacity` writes (or null if
+// `capacity` is 0), and `count` null or valid for writes.
+enum DndStatus dnd_board_violations(const struct DndBoard *board,
+                                    struct DndBoardError *errors,
+                                    size_t capacity,
+                                    size_t *count);
+
+// Writes the count of row `i` to `count`, or -1 if the puzzle hides it.
+//
+// # Safety
+//
+// `board` must be null or a live handle, and `count` null or valid for writes.
+enum DndStatus dnd_board_row_count(const struct DndBoard *board, int32_t i, int32_t *count);
+
+// Writes the count of column `i` to `count`, or -1 if the puzzle hides it.
+//
+// # Safety
+//
+// `board` must be null or a live handle, and `count` null or valid for writes.
+enum DndStatus dnd_board_column_count(const struct DndBoard *board, int32_t i, int32_t *count);
+
+// Writes the cell at (`row`, `col`) to `cell`.
+//
+// # Safety
+//
+// `board` must be null or a live handle, and `cell` null or valid for writes.
+enum DndStatus dnd_board_cell(const struct DndBoard *board,
+                              int32_t row,
+                              int32_t col,
+                              enum DndCell *cell);
+
+// The board in the text format, to be released with `dnd_string_free`.
+//
+// # Safety
+//
+// `board` must be null or a live handle.
+char *dnd_board_to_string(const struct DndBoard *board);
+
+// Releases a string returned by the library; null is ignored.
+//
+// # Safety
+//
+// `s` must be null or a string from `dnd_board_to_string`, not yet released.
+void dnd_string_free(char *s);
+
+#endif  /* DND_SOLVER_H */
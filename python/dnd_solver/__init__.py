@@ -0,0 +1,299 @@
+"""Python bindings for the dungeon puzzle solver, on top of its C API.
+
+Build the shared library with ``cargo build --release`` first. It is looked up in the
+``DND_SOLVER_LIB`` environment variable, then in ``target/release`` of this repository,
+then on the system library path.
+
+    >>> import dnd_solver
+    >>> puzzle, solution = dnd_solver.generate(seed=1)
+    >>> puzzle.count_solutions()
+    1
+    >>> puzzle.solve().to_list() == solution.to_list()
+    True
+    >>> solution.check_solved()
+    >>> puzzle.violations()[0].kind
+    'Unsolved'
+"""
+
+import ctypes
+import ctypes.util
+import os
+from collections import namedtuple
+from pathlib import Path
+
+__all__ = [
+    "Board",
+    "BoardError",
+    "Generated",
+    "SolverError",
+    "parse",
+    "from_code",
+    "generate",
+]
+
+CELLS = ["unknown", "empty", "wall", "monster", "chest"]
+ERROR_KINDS = [
+    None,
+    "Unsolved",
+    "WrongRowCount",
+    "WrongColumnCount",
+    "MonsterNotInDeadEnd",
+    "DeadEndWithNoMonster",
+    "NoTreasureRoomForChest",
+    "CorridorsTooWide",
+    "UnconnectedCorridors",
+]
+VARIABLE_ORDERS = ["reading_order", "most_constrained", "near_clues", "least_slack"]
+VALUE_ORDERS = ["wall_first", "empty_first", "remaining_count"]
+BOARD_SIZE = 8
+
+STATUS_OK = 0
+STATUS_NULL_ARGUMENT = 1
+STATUS_INVALID_PUZZLE = 2
+STATUS_INVALID_OPTION = 3
+STATUS_OUT_OF_BOUNDS = 4
+STATUS_UNSOLVABLE = 5
+STATUS_BUDGET_EXCEEDED = 6
+STATUS_NOT_SOLVED = 7
+STATUS_INTERNAL = 8
+
+
+class SolverError(Exception):
+    """A call into the solver failed. ``status`` is one of the ``STATUS_*`` constants, and
+    the message comes from the library."""
+
+    def __init__(self, status, message):
+        super().__init__(message)
+        self.status = status
+
+
+class BoardError(Exception):
+    """A rule the board breaks. ``row`` and ``col`` are ``None`` where they do not apply.
+
+    Raised by ``check_solved``, with every rule the board breaks in ``violations``."""
+
+    def __init__(self, kind, row=None, col=None, violations=()):
+        where = ", ".join(str(i) for i in (row, col) if i is not None)
+        super().__init__(f"{kind}({where})" if where else kind)
+        self.kind = kind
+        self.row = row
+        self.col = col
+        self.violations = list(violations)
+
+    @classmethod
+    def _from_struct(cls, error):
+        row = error.row if error.row >= 0 else None
+        col = error.col if error.col >= 0 else None
+        return cls(ERROR_KINDS[error.kind], row, col)
+
+
+Generated = namedtuple("Generated", ["puzzle", "solution"])
+
+
+class _SolveOptions(ctypes.Structure):
+    _fields_ = [
+        ("variable_order", ctypes.c_uint32),
+        ("value_order", ctypes.c_uint32),
+        ("node_budget", ctypes.c_uint64),
+    ]
+
+
+class _BoardError(ctypes.Structure):
+    _fields_ = [("kind", ctypes.c_int), ("row", ctypes.c_int32), ("col", ctypes.c_int32)]
+
+
+def _find_library():
+    if "DND_SOLVER_LIB" in os.environ:
+        return os.environ["DND_SOLVER_LIB"]
+    local = Path(__file__).resolve().parents[2] / "target" / "release" / "libzach_dnd_solver.so"
+    if local.exists():
+        return str(local)
+    found = ctypes.util.find_library("zach_dnd_solver")
+    if found is None:
+        raise ImportError("cannot find libzach_dnd_solver; run `cargo build --release`")
+    return found
+
+
+_lib = ctypes.CDLL(_find_library())
+_board = ctypes.c_void_p
+for name, args, result in [
+    ("dnd_last_error", [], ctypes.c_char_p),
+    ("dnd_last_status", [], ctypes.c_int),
+    ("dnd_board_parse", [ctypes.c_char_p], _board),
+    ("dnd_board_from_code", [ctypes.c_char_p], _board),
+    ("dnd_board_clone", [_board], _board),
+    ("dnd_board_free", [_board], None),
+    ("dnd_solve_options_default", [], _SolveOptions),
+    ("dnd_board_solve", [_board, ctypes.POINTER(_SolveOptions)], ctypes.c_int),
+    (
+        "dnd_board_count_solutions",
+        [_board, ctypes.c_uint64, ctypes.c_uint64, ctypes.POINTER(ctypes.c_uint64)],
+        ctypes.c_int,
+    ),
+    (
+        "dnd_generate",
+        [
+            ctypes.c_uint64,
+            ctypes.c_uint32,
+            ctypes.c_bool,
+            ctypes.c_uint64,
+            ctypes.POINTER(_board),
+        ],
+        _board,
+    ),
+    ("dnd_board_check_error", [_board, ctypes.POINTER(_BoardError)], ctypes.c_int),
+    (
+        "dnd_board_violations",
+        [_board, ctypes.POINTER(_BoardError), ctypes.c_size_t, ctypes.POINTER(ctypes.c_size_t)],
+        ctypes.c_int,
+    ),
+    ("dnd_board_row_count", [_board, ctypes.c_int32, ctypes.POINTER(ctypes.c_int32)], ctypes.c_int),
+    (
+        "dnd_board_column_count",
+        [_board, ctypes.c_int32, ctypes.POINTER(ctypes.c_int32)],
+        ctypes.c_int,
+    ),
+    (
+        "dnd_board_cell",
+        [_board, ctypes.c_int32, ctypes.c_int32, ctypes.POINTER(ctypes.c_int)],
+        ctypes.c_int,
+    ),
+    ("dnd_board_to_string", [_board], ctypes.c_void_p),
+    ("dnd_string_free", [ctypes.c_void_p], None),
+]:
+    function = getattr(_lib, name)
+    function.argtypes = args
+    function.restype = result
+
+
+def _error(status=None):
+    """The error left by the last failed call, which returned ``status`` or a null pointer."""
+    if status is None:
+        status = _lib.dnd_last_status()
+    message = _lib.dnd_last_error()
+    return SolverError(status, message.decode() if message else "unknown error")
+
+
+def _check(status):
+    if status != STATUS_OK:
+        raise _error(status)
+
+
+class Board:
+    """A puzzle or a (partially) solved board. Boards are not modified in place."""
+
+    def __init__(self, handle):
+        if not handle:
+            raise _error()
+        self._handle = handle
+
+    def __del__(self):
+        if getattr(self, "_handle", None):
+            _lib.dnd_board_free(self._handle)
+            self._handle = None
+
+    def __str__(self):
+        text = _lib.dnd_board_to_string(self._handle)
+        try:
+            return ctypes.string_at(text).decode()
+        finally:
+            _lib.dnd_string_free(text)
+
+    def __repr__(self):
+        return f"Board.parse({str(self)!r})"
+
+    def __eq__(self, other):
+        return isinstance(other, Board) and str(self) == str(other)
+
+    def __getitem__(self, position):
+        row, col = position
+        cell = ctypes.c_int()
+        _check(_lib.dnd_board_cell(self._handle, row, col, ctypes.byref(cell)))
+        return CELLS[cell.value]
+
+    def _counts(self, function):
+        counts = []
+        for i in range(BOARD_SIZE):
+            count = ctypes.c_int32()
+            _check(function(self._handle, i, ctypes.byref(count)))
+            counts.append(None if count.value < 0 else count.value)
+        return counts
+
+    @property
+    def row_counts(self):
+        """Walls per row, ``None`` for hidden counts."""
+        return self._counts(_lib.dnd_board_row_count)
+
+    @property
+    def column_counts(self):
+        """Walls per column, ``None`` for hidden counts."""
+        return self._counts(_lib.dnd_board_column_count)
+
+    def to_list(self):
+        """The cells as a list of rows of names: ``"unknown"``, ``"wall"`` and so on."""
+        return [[self[r, c] for c in range(BOARD_SIZE)] for r in range(BOARD_SIZE)]
+
+    def solve(self, variable_order="reading_order", value_order="wall_first", node_budget=0):
+        """The first solution. Raises ``SolverError`` if there is none, or if the search
+        takes more than ``node_budget`` nodes (0 for no limit)."""
+        options = _lib.dnd_solve_options_default()
+        options.variable_order = VARIABLE_ORDERS.index(variable_order)
+        options.value_order = VALUE_ORDERS.index(value_order)
+        options.node_budget = node_budget
+        solution = Board(_lib.dnd_board_clone(self._handle))
+        _check(_lib.dnd_board_solve(solution._handle, ctypes.byref(options)))
+        return solution
+
+    def check_solved(self):
+        """Raises ``BoardError`` unless the board is a complete and valid solution."""
+        error = _BoardError()
+        status = _lib.dnd_board_check_error(self._handle, ctypes.byref(error))
+        if status == STATUS_NOT_SOLVED:
+            first = BoardError._from_struct(error)
+            raise BoardError(first.kind, first.row, first.col, self.violations())
+        _check(status)
+
+    def violations(self):
+        """Every rule the board breaks, as ``BoardError``s; empty if it is solved. A board
+        with ``"unknown"`` cells only breaks ``Unsolved``."""
+        count = ctypes.c_size_t()
+        _check(_lib.dnd_board_violations(self._handle, None, 0, ctypes.byref(count)))
+        errors = (_BoardError * count.value)()
+        _check(_lib.dnd_board_violations(self._handle, errors, count.value, ctypes.byref(count)))
+        return [BoardError._from_struct(error) for error in errors]
+
+    def is_solved(self):
+        try:
+            self.check_solved()
+            return True
+        except BoardError:
+            return False
+
+    def count_solutions(self, limit=2, node_budget=0):
+        """The number of solutions, counting no further than ``limit``."""
+        count = ctypes.c_uint64()
+        _check(_lib.dnd_board_count_solutions(self._handle, limit, node_budget, ctypes.byref(count)))
+        return count.value
+
+    @staticmethod
+    def parse(text):
+        """Parses a puzzle in the text format: a bare grid, or a file with one puzzle."""
+        return Board(_lib.dnd_board_parse(text.encode()))
+
+    @staticmethod
+    def from_code(code):
+        return Board(_lib.dnd_board_from_code(code.encode()))
+
+    @staticmethod
+    def generate(seed, rooms=1, hide_counts=False, node_budget=0):
+        """A ``Generated`` puzzle with a unique solution, and that solution; the same seed
+        always gives the same puzzle. Raises ``SolverError`` if it takes more than
+        ``node_budget`` search nodes (0 for no limit)."""
+        solution = _board()
+        puzzle = Board(_lib.dnd_generate(seed, rooms, hide_counts, node_budget, ctypes.byref(solution)))
+        return Generated(puzzle, Board(solution.value))
+
+
+parse = Board.parse
+from_code = Board.from_code
+generate = Board.generate
@@ -0,0 +1,88 @@
+"""Tests of the bindings: run `python -m unittest discover -s python/tests` after
+`cargo build --release`."""
+
+import doctest
+import sys
+import unittest
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parents[1]))
+
+import dnd_solver  # noqa: E402
+
+# Last Call BBS 5-8
+PUZZLE = """\
+ 35344253
+4M   M M 
+4        
+2M       
+4       M
+6M       
+2       M
+3        
+4 M   M M
+"""
+
+SOLUTION = """\
+ 35344253
+4MxxxM,Mx
+4,,,xx,xx
+2Mx,,x,,,
+4xxx,,,xM
+6Mxx,xxxx
+2,,,,,xxM
+3xx,x,,,,
+4xM,xxMxM
+"""
+
+
+def load_tests(loader, tests, ignore):
+    tests.addTests(doctest.DocTestSuite(dnd_solver))
+    return tests
+
+
+class BoardTest(unittest.TestCase):
+    def test_solves_a_puzzle(self):
+        solution = dnd_solver.parse(PUZZLE).solve()
+        self.assertEqual(str(solution), SOLUTION)
+        solution.check_solved()
+        self.assertEqual(solution.violations(), [])
+
+    def test_parse_errors_have_a_status(self):
+        with self.assertRaises(dnd_solver.SolverError) as raised:
+            dnd_solver.parse("not a puzzle")
+        self.assertEqual(raised.exception.status, dnd_solver.STATUS_INVALID_PUZZLE)
+        with self.assertRaises(dnd_solver.SolverError) as raised:
+            dnd_solver.from_code("AYg1")
+        self.assertEqual(raised.exception.status, dnd_solver.STATUS_INVALID_PUZZLE)
+
+    def test_check_solved_reports_every_violation(self):
+        # Two walls too many in the first row
+        board = dnd_solver.parse(SOLUTION.replace("4MxxxM,Mx", "4MxxxMxMx"))
+        violations = [(v.kind, v.row, v.col) for v in board.violations()]
+        self.assertIn(("WrongRowCount", 0, None), violations)
+        with self.assertRaises(dnd_solver.BoardError) as raised:
+            board.check_solved()
+        self.assertEqual(raised.exception.kind, "WrongRowCount")
+        self.assertEqual(
+            [(v.kind, v.row, v.col) for v in raised.exception.violations], violations
+        )
+        self.assertGreater(len(raised.exception.violations), 1)
+
+    def test_budget_errors_have_a_status(self):
+        with self.assertRaises(dnd_solver.SolverError) as raised:
+            dnd_solver.parse(PUZZLE).solve(node_budget=1)
+        self.assertEqual(raised.exception.status, dnd_solver.STATUS_BUDGET_EXCEEDED)
+
+    def test_generate_keeps_the_solution(self):
+        puzzle, solution = dnd_solver.generate(seed=1)
+        self.assertEqual(puzzle.count_solutions(), 1)
+        solution.check_solved()
+        self.assertEqual(puzzle.solve().to_list(), solution.to_list())
+        with self.assertRaises(dnd_solver.SolverError) as raised:
+            dnd_solver.generate(seed=1, node_budget=10)
+        self.assertEqual(raised.exception.status, dnd_solver.STATUS_BUDGET_EXCEEDED)
+
+
+if __name__ == "__main__":
+    unittest.main()
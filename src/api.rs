@@ -0,0 +1,256 @@
+//! The operations offered to front-ends, with JSON requests and results.
+//!
+//! Every endpoint takes a JSON object and answers with a JSON object. Puzzles are passed
+//! as `{"puzzle": "<text>"}`, in the format read by [`parse_puzzles`], or
+//! `{"code": "..."}`.
+//!
+//! * `parse`: the board and its metadata
+//! * `validate`: whether the board is solved, and what is wrong with it
+//! * `solve`: a solution
+//! * `count`: the number of solutions, up to `limit` (default 2)
+//! * `hint`: the next step for a player, or `null`
+//! * `generate`: a new puzzle from `seed`, `rooms` and `hide_counts`
+
+use crate::{
+    format::{parse_puzzles, Metadata},
+    generate::{GenerateOptions, MAX_ROOMS},
+    hint::Hint,
+    json::Json,
+    search::{BudgetExceeded, NodeBudget, Search, SolveOptions, Step},
+    Board, Cell, Index,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ApiError {
+    // The request itself is wrong
+    BadRequest(String),
+    UnknownEndpoint(String),
+    // The request is fine but cannot be carried out, like solving an unsolvable puzzle
+    Failed(String),
+}
+
+impl ApiError {
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::BadRequest(message) | ApiError::Failed(message) => message.clone(),
+            ApiError::UnknownEndpoint(endpoint) => format!("no endpoint `{endpoint}`"),
+        }
+    }
+}
+
+/// Answers a request, given as a JSON object, to one of the endpoints.
+///
+/// `node_budget` bounds the search nodes used by `solve`, `count`, `hint` and `generate`.
+pub fn call(endpoint: &str, request: &str, node_budget: u64) -> Result<Json, ApiError> {
+    let request =
+        Json::parse(request).map_err(|_| ApiError::BadRequest("invalid JSON".to_string()))?;
+    if !matches!(request, Json::Object(_)) {
+        return Err(ApiError::BadRequest("expected a JSON object".to_string()));
+    }
+    let over_budget =
+        |BudgetExceeded| ApiError::Failed(format!("gave up after {node_budget} search nodes"));
+    let mut budget = NodeBudget(node_budget);
+    match endpoint {
+        "parse" => {
+            let (board, metadata) = puzzle_from(&request)?;
+            Ok(object([
+                ("board", board_json(&board)),
+                ("metadata", metadata_json(&metadata)),
+            ]))
+        }
+        "validate" => {
+            let (board, _) = puzzle_from(&request)?;
+            let violations = board
+                .violations()
+                .into_iter()
+                .map(|v| {
+                    object([
+                        ("error", error_name(&v.error)),
+                        ("cells", cells_json(&v.cells)),
+                    ])
+                })
+                .collect::<Vec<_>>();
+            Ok(object([
+                ("solved", violations.is_empty().into()),
+                ("violations", Json::Array(violations)),
+            ]))
+        }
+        "solve" => {
+            let (board, _) = puzzle_from(&request)?;
+            let mut search = Search::new(board, SolveOptions::default());
+            let mut solution = search
+                .next_solution_within(&mut budget)
+                .map_err(over_budget)?
+                .ok_or(ApiError::Failed("the puzzle has no solution".to_string()))?
+                .clone();
+            solution.reveal_counts();
+            Ok(object([
+                ("solution", board_json(&solution)),
+                ("nodes", search.stats().nodes.into()),
+            ]))
+        }
+        "count" => {
+            let (board, _) = puzzle_from(&request)?;
+            let limit = match request.get("limit") {
+                None => 2,
+                Some(limit) => limit
+                    .as_u64()
+                    .ok_or(ApiError::BadRequest("`limit` must be a number".to_string()))?,
+            };
+            let mut search = Search::new(board, SolveOptions::default());
+            let mut solutions = 0;
+            while solutions < limit {
+                match search.step_within(&mut budget).map_err(over_budget)? {
+                    Step::Solution => solutions += 1,
+                    Step::Exhausted => break,
+                    _ => {}
+                }
+            }
+            // Unless the search is over, there may be more solutions than `limit`
+            let finished = search.is_exhausted();
+            Ok(object([
+                ("solutions", solutions.into()),
+                ("finished", finished.into()),
+            ]))
+        }
+        "hint" => {
+            let (board, _) = puzzle_from(&request)?;
+            let hint = match board.hint_within(&mut budget).map_err(over_budget)? {
+                None => Json::Null,
+                Some(Hint::Deduction {
+                    row,
+                    col,
+                    value,
+                    reason,
+                }) => object([
+                    ("kind", "deduction".into()),
+                    ("row", (row as u64).into()),
+                    ("col", (col as u64).into()),
+                    ("value", value.name().into()),
+                    ("reason", format!("{reason:?}").as_str().into()),
+                ]),
+                Some(Hint::Conflict(cells)) => {
+                    object([("kind", "conflict".into()), ("cells", cells_json(&cells))])
+                }
+            };
+            Ok(object([("hint", hint)]))
+        }
+        "generate" => {
+            let defaults = GenerateOptions::default();
+            let field = |key: &str| request.get(key).filter(|v| **v != Json::Null);
+            let seed = field("seed").map_or(Some(defaults.seed), Json::as_u64);
+            let rooms = field("rooms").map_or(Some(defaults.rooms as u64), Json::as_u64);
+            let hide_counts =
+                field("hide_counts").map_or(Some(defaults.hide_counts), Json::as_bool);
+            let (Some(seed), Some(rooms), Some(hide_counts)) = (seed, rooms, hide_counts) else {
+                return Err(ApiError::BadRequest(
+                    "`seed` and `rooms` must be numbers and `hide_counts` a boolean".to_string(),
+                ));
+            };
+            if rooms as usize > MAX_ROOMS {
+                return Err(ApiError::BadRequest(format!(
+                    "at most {MAX_ROOMS} rooms are supported"
+                )));
+            }
+            let options = GenerateOptions {
+                seed,
+                rooms: rooms as usize,
+                hide_counts,
+            };
+            let generated = Board::generate_within(&options, &mut budget).map_err(over_budget)?;
+            Ok(object([
+                ("puzzle", board_json(&generated.puzzle)),
+                ("solution", board_json(&generated.solution)),
+            ]))
+        }
+        _ => Err(ApiError::UnknownEndpoint(endpoint.to_string())),
+    }
+}
+
+fn puzzle_from(request: &Json) -> Result<(Board, Metadata), ApiError> {
+    if let Some(code) = request.get("code").and_then(Json::as_str) {
+        let board = Board::from_code(code)
+            .map_err(|e| ApiError::BadRequest(format!("invalid puzzle code: {e:?}")))?;
+        return Ok((board, Metadata::default()));
+    }
+    let text = request
+        .get("puzzle")
+        .and_then(Json::as_str)
+        .ok_or(ApiError::BadRequest(
+            "expected a `puzzle` or a `code`".to_string(),
+        ))?;
+    let mut puzzles =
+        parse_puzzles(text).map_err(|e| ApiError::BadRequest(format!("invalid puzzle: {e:?}")))?;
+    if puzzles.len() != 1 {
+        return Err(ApiError::BadRequest(format!(
+            "expected one puzzle, got {}",
+            puzzles.len()
+        )));
+    }
+    let puzzle = puzzles.pop().unwrap();
+    Ok((puzzle.board, puzzle.metadata))
+}
+
+pub(crate) fn object<const N: usize>(entries: [(&str, Json); N]) -> Json {
+    Json::Object(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+pub(crate) fn error(message: &str) -> Json {
+    object([("error", message.into())])
+}
+
+fn board_json(board: &Board) -> Json {
+    let counts = |counts: &[Option<u8>]| {
+        Json::Array(counts.iter().map(|&n| n.map(u64::from).into()).collect())
+    };
+    let rows = |f: &dyn Fn(usize, usize) -> Json| {
+        Json::Array(
+            (0..board.cells.len())
+                .map(|r| Json::Array((0..board.cells[r].len()).map(|c| f(r, c)).collect()))
+                .collect(),
+        )
+    };
+    object([
+        ("text", board.to_string().as_str().into()),
+        ("code", board.to_code().as_str().into()),
+        ("column_counts", counts(&board.column_counts)),
+        ("row_counts", counts(&board.row_counts)),
+        ("cells", rows(&|r, c| board.cells[r][c].name().into())),
+        (
+            "givens",
+            rows(&|r, c| {
+                (board.givens[r][c] && !matches!(board.cells[r][c], Cell::Unknown)).into()
+            }),
+        ),
+    ])
+}
+
+fn metadata_json(metadata: &Metadata) -> Json {
+    object([
+        ("title", metadata.title.as_deref().into()),
+        ("author", metadata.author.as_deref().into()),
+        ("source", metadata.source.as_deref().into()),
+        ("difficulty", metadata.difficulty.as_deref().into()),
+        ("notes", metadata.notes.as_deref().into()),
+    ])
+}
+
+fn cells_json(cells: &[(Index, Index)]) -> Json {
+    Json::Array(
+        cells
+            .iter()
+            .map(|&(r, c)| Json::Array(vec![(r as u64).into(), (c as u64).into()]))
+            .collect(),
+    )
+}
+
+// The variant name, without the coordinates.
+fn error_name(error: &crate::BoardError) -> Json {
+    let name = format!("{error:?}");
+    name.split('(').next().unwrap_or_default().into()
+}
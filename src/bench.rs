@@ -0,0 +1,248 @@
+//! Timing the solver's strategies over a corpus of puzzles.
+//!
+//! Each strategy solves every puzzle a few times; the time of a puzzle is the median of
+//! its runs, and the percentiles are taken over those per-puzzle times. The baseline is
+//! the plain backtracking [`Board::solve`] started out with: [`SolverKind::Backtracking`]
+//! in reading order, walls first, pruning only with [`Board::basic_checks`]. Each
+//! strategy's speedup is the baseline's total time divided by its own.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    api::object,
+    json::Json,
+    search::{SolveOptions, ValueOrder, VariableOrder},
+    solver::SolverKind,
+    Board,
+};
+
+const VERSION: u64 = 2;
+
+#[derive(Copy, Clone, Debug)]
+pub struct BenchOptions {
+    /// Times each puzzle is solved by each strategy.
+    pub runs: usize,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions { runs: 5 }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PuzzleResult {
+    pub name: String,
+    pub solved: bool,
+    pub nodes: u64,
+    pub backtracks: u64,
+    /// Median over the runs.
+    pub time: Duration,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Strategy {
+    pub solver: SolverKind,
+    pub options: SolveOptions,
+}
+
+#[derive(Clone, Debug)]
+pub struct StrategyResult {
+    pub strategy: Strategy,
+    pub puzzles: Vec<PuzzleResult>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub runs: usize,
+    /// The baseline comes first.
+    pub strategies: Vec<StrategyResult>,
+}
+
+/// The baseline, then the propagating solver with every combination of the heuristics,
+/// then the other solvers with the default heuristics.
+pub fn strategies() -> Vec<Strategy> {
+    let strategy = |solver, options| Strategy { solver, options };
+    let baseline = SolveOptions {
+        variable_order: VariableOrder::ReadingOrder,
+        value_order: ValueOrder::WallFirst,
+    };
+    let mut all = vec![strategy(SolverKind::Backtracking, baseline)];
+    for variable_order in VariableOrder::ALL {
+        for value_order in ValueOrder::ALL {
+            let options = SolveOptions {
+                variable_order,
+                value_order,
+            };
+            all.push(strategy(SolverKind::Propagation, options));
+        }
+    }
+    for solver in [SolverKind::Sat, SolverKind::Parallel] {
+        all.push(strategy(solver, SolveOptions::default()));
+    }
+    all
+}
+
+/// Runs the strategies over the named puzzles; the first strategy is the baseline.
+pub fn bench(
+    puzzles: &[(String, Board)],
+    strategies: &[Strategy],
+    options: &BenchOptions,
+) -> Report {
+    let runs = options.runs.max(1);
+    let strategies = strategies
+        .iter()
+        .map(|&strategy| StrategyResult {
+            strategy,
+            puzzles: puzzles
+                .iter()
+                .map(|(name, puzzle)| {
+                    let mut times = Vec::with_capacity(runs);
+                    let (mut solved, mut stats) = (false, Default::default());
+                    for _ in 0..runs {
+                        let mut solver = strategy.solver.solver(strategy.options);
+                        let start = Instant::now();
+                        solved = solver.solve(puzzle).is_ok();
+                        times.push(start.elapsed());
+                        stats = solver.stats();
+                    }
+                    times.sort();
+                    PuzzleResult {
+                        name: name.clone(),
+                        solved,
+                        nodes: stats.nodes,
+                        backtracks: stats.backtracks,
+                        time: times[times.len() / 2],
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+    Report { runs, strategies }
+}
+
+impl Strategy {
+    pub fn name(&self) -> String {
+        let SolveOptions {
+            variable_order,
+            value_order,
+        } = self.options;
+        format!("{} {variable_order:?}/{value_order:?}", self.solver.name())
+    }
+}
+
+impl StrategyResult {
+    pub fn name(&self) -> String {
+        self.strategy.name()
+    }
+
+    pub fn solved(&self) -> usize {
+        self.puzzles.iter().filter(|p| p.solved).count()
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.puzzles.iter().map(|p| p.nodes).sum()
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.puzzles.iter().map(|p| p.time).sum()
+    }
+
+    /// The per-puzzle time that `p` percent of the puzzles do not exceed.
+    pub fn percentile(&self, p: f64) -> Duration {
+        let mut times = self.puzzles.iter().map(|p| p.time).collect::<Vec<_>>();
+        times.sort();
+        // Nearest rank
+        let rank = (p / 100.0 * times.len() as f64).ceil() as usize;
+        times.get(rank.max(1) - 1).copied().unwrap_or_default()
+    }
+}
+
+impl Report {
+    pub fn baseline(&self) -> &StrategyResult {
+        &self.strategies[0]
+    }
+
+    /// How many times faster than the baseline the strategy is, over the whole corpus.
+    pub fn speedup(&self, strategy: &StrategyResult) -> f64 {
+        self.baseline().total_time().as_secs_f64() / strategy.total_time().as_secs_f64()
+    }
+
+    pub fn to_json(&self) -> Json {
+        let ms = |time: Duration| Json::Number(time.as_secs_f64() * 1000.0);
+        let strategies = self
+            .strategies
+            .iter()
+            .map(|strategy| {
+                let puzzles = strategy
+                    .puzzles
+                    .iter()
+                    .map(|p| {
+                        object([
+                            ("name", p.name.as_str().into()),
+                            ("solved", p.solved.into()),
+                            ("nodes", p.nodes.into()),
+                            ("backtracks", p.backtracks.into()),
+                            ("time_ms", ms(p.time)),
+                        ])
+                    })
+                    .collect();
+                let options = strategy.strategy.options;
+                object([
+                    ("name", strategy.name().as_str().into()),
+                    ("solver", strategy.strategy.solver.name().into()),
+                    (
+                        "variable_order",
+                        format!("{:?}", options.variable_order).as_str().into(),
+                    ),
+                    (
+                        "value_order",
+                        format!("{:?}", options.value_order).as_str().into(),
+                    ),
+                    ("solved", (strategy.solved() as u64).into()),
+                    ("nodes", strategy.nodes().into()),
+                    ("total_ms", ms(strategy.total_time())),
+                    ("median_ms", ms(strategy.percentile(50.0))),
+                    ("p90_ms", ms(strategy.percentile(90.0))),
+                    ("p99_ms", ms(strategy.percentile(99.0))),
+                    ("speedup", Json::Number(self.speedup(strategy))),
+                    ("puzzles", Json::Array(puzzles)),
+                ])
+            })
+            .collect();
+        object([
+            ("version", VERSION.into()),
+            ("runs", (self.runs as u64).into()),
+            ("baseline", self.baseline().name().as_str().into()),
+            ("strategies", Json::Array(strategies)),
+        ])
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<50} {:>6} {:>10} {:>11} {:>11} {:>11} {:>8}",
+            "strategy", "solved", "nodes", "median (ms)", "p90 (ms)", "p99 (ms)", "speedup"
+        )?;
+        let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+        for strategy in &self.strategies {
+            writeln!(
+                f,
+                "{:<50} {:>6} {:>10} {:>11.3} {:>11.3} {:>11.3} {:>7.2}x",
+                strategy.name(),
+                strategy.solved(),
+                strategy.nodes(),
+                ms(strategy.percentile(50.0)),
+                ms(strategy.percentile(90.0)),
+                ms(strategy.percentile(99.0)),
+                self.speedup(strategy)
+            )?;
+        }
+        Ok(())
+    }
+}
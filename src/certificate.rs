@@ -0,0 +1,487 @@
+//! Certificates that a puzzle has exactly one solution.
+//!
+//! A certificate is the solution together with a proof tree. Each inner node splits on an
+//! `Unknown` cell, with one subtree for `Wall` and one for `Empty`; each leaf either holds
+//! the solution or a board that breaks the rules. Cells forced by propagation show up as
+//! splits where one side is refuted straight away.
+//!
+//! [`Certificate::check`] does not use the search, the propagation or the pruning code: it
+//! only replays the splits, and accepts a refuted leaf if [`Board::check_solved`] rejects
+//! its complete board, or if a handful of simple rules in this module are broken already.
+//! Boards that the search refutes in a cleverer way are split further until they are.
+
+use std::fmt;
+
+use crate::{
+    search::{ValueOrder, VariableOrder},
+    Board, Cell, Index, ParseError, BOARD_SIZE,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Proof {
+    /// The board is complete and is the solution.
+    Solution,
+    /// The board breaks the rules.
+    Refuted,
+    Split {
+        row: Index,
+        col: Index,
+        wall: Box<Proof>,
+        empty: Box<Proof>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    pub puzzle: Board,
+    pub solution: Board,
+    pub proof: Proof,
+}
+
+#[derive(Debug)]
+pub enum CertifyError {
+    NoSolution,
+    MultipleSolutions,
+}
+
+// Leaves and splits are numbered in preorder.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CheckError {
+    // The puzzle has cells placed that are not givens
+    NotAPuzzle,
+    // The solution does not solve the puzzle
+    NotASolution,
+    // A split on a cell that is already known
+    InvalidSplit(usize),
+    // A `Refuted` leaf whose board could still be solved
+    NotRefuted(usize),
+    // A `Solution` leaf whose board is not the solution
+    WrongSolution(usize),
+    // The tree has no `Solution` leaf, or more than one
+    SolutionCount(usize),
+}
+
+impl Board {
+    /// Proves that the puzzle, without any placed cells, has exactly one solution.
+    pub fn certify(&self) -> Result<Certificate, CertifyError> {
+        let puzzle = self.puzzle();
+        let mut solutions = Vec::new();
+        let proof = prove(&mut puzzle.clone(), &mut solutions);
+        match solutions.len() {
+            0 => Err(CertifyError::NoSolution),
+            1 => Ok(Certificate {
+                puzzle,
+                solution: solutions.pop().unwrap(),
+                proof,
+            }),
+            _ => Err(CertifyError::MultipleSolutions),
+        }
+    }
+}
+
+// Builds the proof tree below `board`, which is left as it was. Gives up with a partial
+// tree once a second solution turns up, since the puzzle cannot be certified then.
+fn prove(board: &mut Board, solutions: &mut Vec<Board>) -> Proof {
+    if solutions.len() > 1 {
+        return Proof::Refuted;
+    }
+    if board.maybe_solvable().is_err() {
+        return refute(board);
+    }
+    let Ok(forced) = board.deductions() else {
+        return refute(board);
+    };
+
+    // Split on a forced cell if there is one, since its other side is refuted at once
+    let split = match forced.first() {
+        Some(&((row, col), value)) => Some(((row, col), [value, opposite(value)])),
+        None => board
+            .branching_cell(VariableOrder::default())
+            .map(|(row, col)| {
+                (
+                    (row, col),
+                    board.value_order(row, col, ValueOrder::default()),
+                )
+            }),
+    };
+    let Some(((row, col), values)) = split else {
+        if board.check_solved().is_err() {
+            return refute(board);
+        }
+        solutions.push(board.clone());
+        return Proof::Solution;
+    };
+
+    let mut wall = Proof::Refuted;
+    let mut empty = Proof::Refuted;
+    for value in values {
+        board.cells[row as usize][col as usize] = value;
+        let proof = prove(board, solutions);
+        match value {
+            Cell::Wall => wall = proof,
+            _ => empty = proof,
+        }
+    }
+    board.cells[row as usize][col as usize] = Cell::Unknown;
+    Proof::Split {
+        row,
+        col,
+        wall: Box::new(wall),
+        empty: Box::new(empty),
+    }
+}
+
+// Proves that `board`, which the search has found to have no solution, has none, with
+// every leaf rejected by `breaks_rules` or `check_solved`. Left as it was.
+fn refute(board: &mut Board) -> Proof {
+    if breaks_rules(board) {
+        return Proof::Refuted;
+    }
+    // Prefer a cell with a side that breaks the rules at once, to keep the tree small
+    let mut best = None;
+    for (row, col) in
+        (0..BOARD_SIZE as Index).flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+    {
+        let (r, c) = (row as usize, col as usize);
+        if board.cells[r][c] != Cell::Unknown {
+            continue;
+        }
+        let mut refuted = 0;
+        for value in [Cell::Wall, Cell::Empty] {
+            board.cells[r][c] = value;
+            refuted += breaks_rules(board) as usize;
+        }
+        board.cells[r][c] = Cell::Unknown;
+        if best.is_none_or(|(_, most)| refuted > most) {
+            best = Some(((row, col), refuted));
+        }
+        if refuted == 2 {
+            break;
+        }
+    }
+    let Some(((row, col), _)) = best else {
+        return Proof::Refuted;
+    };
+    let (r, c) = (row as usize, col as usize);
+    board.cells[r][c] = Cell::Wall;
+    let wall = refute(board);
+    board.cells[r][c] = Cell::Empty;
+    let empty = refute(board);
+    board.cells[r][c] = Cell::Unknown;
+    Proof::Split {
+        row,
+        col,
+        wall: Box::new(wall),
+        empty: Box::new(empty),
+    }
+}
+
+// Whether the rules are broken however the `Unknown` cells are filled in, judging only by
+// what is certain: too many or too few walls left for a count, a monster that cannot be in
+// a dead end, another floor cell in a dead end, a chest with no room to be in, a 2x2 floor
+// block too far from any chest for a treasure room to allow it, or floor cells walled off
+// from each other.
+//
+// This is kept apart from `maybe_solvable`, so that checking a certificate does not rely
+// on the code that found it.
+fn breaks_rules(board: &Board) -> bool {
+    let neighbours = |r: Index, c: Index| [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)];
+    let all = (0..BOARD_SIZE as Index).flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)));
+
+    for i in 0..BOARD_SIZE {
+        let row = board.cells[i];
+        let column = std::array::from_fn::<_, BOARD_SIZE, _>(|j| board.cells[j][i]);
+        for (count, line) in [(board.row_counts[i], row), (board.column_counts[i], column)] {
+            let walls = line.iter().filter(|&&cell| cell == Cell::Wall).count();
+            let unknown = line.iter().filter(|&&cell| cell == Cell::Unknown).count();
+            if count.is_some_and(|n| walls > n as usize || walls + unknown < n as usize) {
+                return true;
+            }
+        }
+    }
+
+    for (r, c) in all.clone() {
+        let around = neighbours(r, c).map(|(r, c)| board.at(r, c));
+        let walls = around.iter().filter(|&&cell| cell == Cell::Wall).count();
+        let open = around
+            .iter()
+            .filter(|&&cell| !matches!(cell, Cell::Wall | Cell::Unknown))
+            .count();
+        match board.at(r, c) {
+            Cell::Monster if walls == 4 || open > 1 => return true,
+            Cell::Empty | Cell::Chest if walls == 3 => return true,
+            _ => {}
+        }
+
+        // The 3x3 rooms holding the chest, with nothing but floor around it
+        if board.at(r, c) == Cell::Chest {
+            let fits = (r - 2..=r).any(|top| {
+                (c - 2..=c).any(|left| {
+                    (top..top + 3).all(|i| {
+                        (left..left + 3).all(|j| {
+                            board.is_in_bounds(i, j)
+                                && ((i, j) == (r, c)
+                                    || matches!(board.at(i, j), Cell::Empty | Cell::Unknown))
+                        })
+                    })
+                })
+            });
+            if !fits {
+                return true;
+            }
+        }
+
+        // A room allows the blocks next to it, whose corners are within 3 cells up or left
+        // and 4 cells down or right of its chest
+        let block = [(r, c), (r, c + 1), (r + 1, c), (r + 1, c + 1)];
+        if block.iter().all(|&(r, c)| board.at(r, c) == Cell::Empty)
+            && !(r - 3..=r + 4).any(|i| (c - 3..=c + 4).any(|j| board.at(i, j) == Cell::Chest))
+        {
+            return true;
+        }
+    }
+
+    // Every floor cell must be reachable from the first one without crossing a wall
+    let mut floor = all.filter(|&(r, c)| !matches!(board.at(r, c), Cell::Wall | Cell::Unknown));
+    let Some(first) = floor.next() else {
+        return false;
+    };
+    let mut reached = [[false; BOARD_SIZE]; BOARD_SIZE];
+    reached[first.0 as usize][first.1 as usize] = true;
+    let mut to_visit = vec![first];
+    while let Some((r, c)) = to_visit.pop() {
+        for (r, c) in neighbours(r, c) {
+            if board.at(r, c) != Cell::Wall && !reached[r as usize][c as usize] {
+                reached[r as usize][c as usize] = true;
+                to_visit.push((r, c));
+            }
+        }
+    }
+    floor.any(|(r, c)| !reached[r as usize][c as usize])
+}
+
+fn opposite(value: Cell) -> Cell {
+    match value {
+        Cell::Wall => Cell::Empty,
+        _ => Cell::Wall,
+    }
+}
+
+impl Certificate {
+    pub fn check(&self) -> Result<(), CheckError> {
+        // Anything but the givens would be a clue the proof could lean on
+        if self.puzzle != self.puzzle.puzzle() {
+            return Err(CheckError::NotAPuzzle);
+        }
+        let agrees = (0..BOARD_SIZE).all(|r| {
+            (0..BOARD_SIZE).all(|c| {
+                let given = self.puzzle.cells[r][c];
+                matches!(given, Cell::Unknown) || given == self.solution.cells[r][c]
+            })
+        });
+        if !agrees
+            || self.puzzle.row_counts != self.solution.row_counts
+            || self.puzzle.column_counts != self.solution.column_counts
+            || self.solution.check_solved().is_err()
+        {
+            return Err(CheckError::NotASolution);
+        }
+
+        let mut checker = Checker {
+            solution: &self.solution,
+            board: self.puzzle.clone(),
+            node: 0,
+            solutions: 0,
+        };
+        checker.check(&self.proof)?;
+        match checker.solutions {
+            1 => Ok(()),
+            n => Err(CheckError::SolutionCount(n)),
+        }
+    }
+
+    pub fn from_string(spec: &str) -> Result<Certificate, ParseError> {
+        let mut lines = spec.lines();
+        if lines.next() != Some("certificate 1") {
+            return Err(ParseError);
+        }
+        let mut grid = || {
+            Board::from_string(
+                &lines
+                    .by_ref()
+                    .take(BOARD_SIZE + 1)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        };
+        let puzzle = grid()?;
+        let solution = grid()?;
+        let tokens = lines
+            .next()
+            .and_then(|line| line.strip_prefix("proof "))
+            .ok_or(ParseError)?;
+        let mut tokens = tokens.split(' ');
+        let proof = parse_proof(&mut tokens, 0)?;
+        if tokens.next().is_some() || lines.next().is_some() {
+            return Err(ParseError);
+        }
+        Ok(Certificate {
+            puzzle,
+            solution,
+            proof,
+        })
+    }
+}
+
+struct Checker<'a> {
+    solution: &'a Board,
+    board: Board,
+    node: usize,
+    solutions: usize,
+}
+
+impl Checker<'_> {
+    fn check(&mut self, proof: &Proof) -> Result<(), CheckError> {
+        let node = self.node;
+        self.node += 1;
+        match proof {
+            Proof::Solution => {
+                if self.board.cells != self.solution.cells {
+                    return Err(CheckError::WrongSolution(node));
+                }
+                self.solutions += 1;
+            }
+            Proof::Refuted => {
+                let complete = self
+                    .board
+                    .cells
+                    .iter()
+                    .flatten()
+                    .all(|cell| !matches!(cell, Cell::Unknown));
+                let broken =
+                    breaks_rules(&self.board) || (complete && self.board.check_solved().is_err());
+                if !broken {
+                    return Err(CheckError::NotRefuted(node));
+                }
+            }
+            &Proof::Split {
+                row,
+                col,
+                ref wall,
+                ref empty,
+            } => {
+                if !self.board.is_in_bounds(row, col)
+                    || !matches!(self.board.at(row, col), Cell::Unknown)
+                {
+                    return Err(CheckError::InvalidSplit(node));
+                }
+                let (r, c) = (row as usize, col as usize);
+                self.board.cells[r][c] = Cell::Wall;
+                self.check(wall)?;
+                self.board.cells[r][c] = Cell::Empty;
+                self.check(empty)?;
+                self.board.cells[r][c] = Cell::Unknown;
+            }
+        }
+        Ok(())
+    }
+}
+
+// A proof never splits on the same cell twice along a path, so this bounds its depth.
+const MAX_SPLITS: usize = BOARD_SIZE * BOARD_SIZE;
+
+fn parse_proof<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    splits: usize,
+) -> Result<Proof, ParseError> {
+    Ok(match tokens.next().ok_or(ParseError)? {
+        "s" => Proof::Solution,
+        "x" => Proof::Refuted,
+        _ if splits == MAX_SPLITS => return Err(ParseError),
+        split => {
+            let (row, col) = split.split_once(',').ok_or(ParseError)?;
+            Proof::Split {
+                row: row.parse().map_err(|_| ParseError)?,
+                col: col.parse().map_err(|_| ParseError)?,
+                wall: Box::new(parse_proof(tokens, splits + 1)?),
+                empty: Box::new(parse_proof(tokens, splits + 1)?),
+            }
+        }
+    })
+}
+
+/// Preorder, with `s` for a solution, `x` for a refuted board and `row,col` for a split
+/// followed by its `Wall` and `Empty` subtrees.
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Proof::Solution => write!(f, "s"),
+            Proof::Refuted => write!(f, "x"),
+            Proof::Split {
+                row,
+                col,
+                wall,
+                empty,
+            } => write!(f, "{row},{col} {wall} {empty}"),
+        }
+    }
+}
+
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "certificate 1\n{}{}proof {}\n",
+            self.puzzle, self.solution, self.proof
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Last Call BBS 5-8 with the given column counts, and a given wall at (6, 0)
+    // that makes it unique even with three of them hidden
+    fn puzzle(column_counts: &str) -> Board {
+        Board::from_string(&format!(
+            " {column_counts}\n4M   M M \n4        \n2M       \n4       M\n\
+             6M       \n2       M\n3#       \n4 M   M M"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn certifies_a_unique_puzzle() {
+        let certificate = puzzle("35344253").certify().unwrap();
+        assert_eq!(certificate.check(), Ok(()));
+        let text = certificate.to_string();
+        assert_eq!(Certificate::from_string(&text).unwrap(), certificate);
+    }
+
+    #[test]
+    fn gives_up_on_a_puzzle_with_many_solutions() {
+        let mut board = puzzle("35344253");
+        board.row_counts = [None; BOARD_SIZE];
+        board.column_counts = [None; BOARD_SIZE];
+        assert_eq!(board.count_solutions(3), 3);
+        assert!(matches!(
+            board.certify(),
+            Err(CertifyError::MultipleSolutions)
+        ));
+    }
+
+    #[test]
+    fn rejects_placed_cells_in_the_puzzle() {
+        let certificate = puzzle("???44253").certify().unwrap();
+        assert_eq!(certificate.check(), Ok(()));
+
+        // Without the given wall, the puzzle has two solutions
+        let text = certificate.to_string();
+        let tampered = text.replacen("\n3#", "\n3x", 1);
+        assert_ne!(tampered, text);
+        let tampered = Certificate::from_string(&tampered).unwrap();
+        assert_eq!(tampered.puzzle.puzzle().count_solutions(3), 2);
+        assert_eq!(tampered.check(), Err(CheckError::NotAPuzzle));
+    }
+}
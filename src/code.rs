@@ -0,0 +1,304 @@
+//! Compact, URL-safe puzzle codes.
+//!
+//! A code is the base64url (no padding) encoding of the following bit stream:
+//!
+//! * 8 bits: format version (currently 1)
+//! * 4 + 4 bits: number of rows and columns
+//! * 4 bits per count: column counts, then row counts; 15 for a hidden count
+//! * 1 bit per cell, in reading order: whether the cell is known
+//! * 2 bits per known cell: `Monster`, `Chest`, `Wall` or `Empty`
+//! * zero padding up to a whole byte
+//! * 16 bits: Fletcher-16 checksum of all the preceding bytes
+//!
+//! Only the givens are encoded; cells placed by the player or the solver are left out.
+
+use crate::{Board, Cell, BOARD_SIZE};
+
+const VERSION: u8 = 1;
+const HIDDEN_COUNT: u32 = 15;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug)]
+pub enum CodeError {
+    InvalidCharacter(char),
+    WrongLength,
+    BadChecksum,
+    UnsupportedVersion(u8),
+    UnsupportedSize(u8, u8),
+    InvalidCount(u8),
+}
+
+impl Board {
+    pub fn to_code(&self) -> String {
+        let puzzle = self.puzzle();
+        let mut bits = BitWriter::default();
+        bits.write(VERSION as u32, 8);
+        bits.write(BOARD_SIZE as u32, 4);
+        bits.write(BOARD_SIZE as u32, 4);
+        for &count in puzzle.column_counts.iter().chain(&puzzle.row_counts) {
+            bits.write(count.map_or(HIDDEN_COUNT, |n| n as u32), 4);
+        }
+        for cell in puzzle.cells.iter().flatten() {
+            bits.write(!matches!(cell, Cell::Unknown) as u32, 1);
+        }
+        for cell in puzzle.cells.iter().flatten() {
+            let kind = match cell {
+                Cell::Unknown => continue,
+                Cell::Monster => 0,
+                Cell::Chest => 1,
+                Cell::Wall => 2,
+                Cell::Empty => 3,
+            };
+            bits.write(kind, 2);
+        }
+
+        let mut bytes = bits.finish();
+        let checksum = fletcher16(&bytes);
+        bytes.extend(checksum.to_be_bytes());
+        base64_encode(&bytes)
+    }
+
+    pub fn from_code(code: &str) -> Result<Board, CodeError> {
+        let bytes = base64_decode(code.trim())?;
+        if bytes.len() < 3 {
+            return Err(CodeError::WrongLength);
+        }
+        let (payload, checksum) = bytes.split_at(bytes.len() - 2);
+        if fletcher16(payload).to_be_bytes() != checksum {
+            return Err(CodeError::BadChecksum);
+        }
+
+        let mut bits = BitReader::new(payload);
+        let version = bits.read(8)? as u8;
+        if version != VERSION {
+            return Err(CodeError::UnsupportedVersion(version));
+        }
+        let (rows, cols) = (bits.read(4)? as u8, bits.read(4)? as u8);
+        if (rows as usize, cols as usize) != (BOARD_SIZE, BOARD_SIZE) {
+            return Err(CodeError::UnsupportedSize(rows, cols));
+        }
+
+        let mut column_counts = [None; BOARD_SIZE];
+        let mut row_counts = [None; BOARD_SIZE];
+        for count in column_counts.iter_mut().chain(row_counts.iter_mut()) {
+            *count = match bits.read(4)? {
+                HIDDEN_COUNT => None,
+                n if n as usize <= BOARD_SIZE => Some(n as u8),
+                n => return Err(CodeError::InvalidCount(n as u8)),
+            };
+        }
+
+        let mut known = [[false; BOARD_SIZE]; BOARD_SIZE];
+        for flag in known.iter_mut().flatten() {
+            *flag = bits.read(1)? == 1;
+        }
+        let mut cells = [[Cell::Unknown; BOARD_SIZE]; BOARD_SIZE];
+        for (cell, &known) in cells.iter_mut().flatten().zip(known.iter().flatten()) {
+            if !known {
+                continue;
+            }
+            *cell = match bits.read(2)? {
+                0 => Cell::Monster,
+                1 => Cell::Chest,
+                2 => Cell::Wall,
+                _ => Cell::Empty,
+            };
+        }
+        if !bits.only_padding_left() {
+            return Err(CodeError::WrongLength);
+        }
+
+        Ok(Board {
+            cells,
+            column_counts,
+            row_counts,
+            givens: known,
+        })
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    used: u32, // bits used in the last byte
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, width: u32) {
+        for i in (0..width).rev() {
+            if self.used.is_multiple_of(8) {
+                self.bytes.push(0);
+                self.used = 0;
+            }
+            let bit = ((value >> i) & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (7 - self.used);
+            self.used += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize, // in bits
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, pos: 0 }
+    }
+
+    fn read(&mut self, width: u32) -> Result<u32, CodeError> {
+        let mut value = 0;
+        for _ in 0..width {
+            let byte = self.bytes.get(self.pos / 8).ok_or(CodeError::WrongLength)?;
+            let bit = (byte >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn only_padding_left(&self) -> bool {
+        self.bytes.len() == self.pos.div_ceil(8)
+    }
+}
+
+fn fletcher16(bytes: &[u8]) -> u16 {
+    let (mut a, mut b) = (0u16, 0u16);
+    for &byte in bytes {
+        a = (a + byte as u16) % 255;
+        b = (b + a) % 255;
+    }
+    (b << 8) | a
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn base64_decode(code: &str) -> Result<Vec<u8>, CodeError> {
+    if code.len() % 4 == 1 {
+        return Err(CodeError::WrongLength);
+    }
+    let sextets = code
+        .chars()
+        .map(|ch| {
+            ALPHABET
+                .iter()
+                .position(|&a| a as char == ch)
+                .map(|v| v as u32)
+                .ok_or(CodeError::InvalidCharacter(ch))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut out = Vec::with_capacity(sextets.len() * 3 / 4);
+    for chunk in sextets.chunks(4) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &s)| n | s << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Last Call BBS 5-8, with a hidden count and a given wall
+    fn puzzle() -> Board {
+        Board::from_string(
+            " 3534?253\n4M   M M \n4        \n2M       \n4       M\n\
+             6M       \n2       M\n3#       \n4 M.  M M",
+        )
+        .unwrap()
+    }
+
+    // A code with a valid checksum for `payload`.
+    fn code(payload: &[u8]) -> String {
+        let mut bytes = payload.to_vec();
+        bytes.extend(fletcher16(payload).to_be_bytes());
+        base64_encode(&bytes)
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        let puzzle = puzzle();
+        let code = puzzle.to_code();
+        assert!(code.bytes().all(|b| ALPHABET.contains(&b)));
+        assert_eq!(Board::from_code(&code).unwrap(), puzzle);
+        assert_eq!(Board::from_code(&format!(" {code}\n")).unwrap(), puzzle);
+    }
+
+    #[test]
+    fn placed_cells_are_left_out() {
+        let mut solved = puzzle();
+        solved.solve().unwrap();
+        // Solving also fills in the hidden count
+        solved.column_counts = puzzle().column_counts;
+        assert_eq!(solved.to_code(), puzzle().to_code());
+    }
+
+    #[test]
+    fn rejects_invalid_codes() {
+        let code = puzzle().to_code();
+        assert!(matches!(
+            Board::from_code(&code.replacen('A', "*", 1)),
+            Err(CodeError::InvalidCharacter('*'))
+        ));
+        assert!(matches!(
+            Board::from_code(&code[..code.len() - 1]),
+            Err(CodeError::WrongLength | CodeError::BadChecksum)
+        ));
+        assert!(matches!(
+            Board::from_code("AA"),
+            Err(CodeError::WrongLength)
+        ));
+
+        let mut corrupted = code.into_bytes();
+        corrupted[10] = if corrupted[10] == b'A' { b'B' } else { b'A' };
+        assert!(matches!(
+            Board::from_code(&String::from_utf8(corrupted).unwrap()),
+            Err(CodeError::BadChecksum)
+        ));
+    }
+
+    #[test]
+    fn rejects_codes_it_cannot_read() {
+        assert!(matches!(
+            Board::from_code(&code(&[2, 0x88])),
+            Err(CodeError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            Board::from_code(&code(&[VERSION, 0x99])),
+            Err(CodeError::UnsupportedSize(9, 9))
+        ));
+        // A column count of 9
+        assert!(matches!(
+            Board::from_code(&code(&[VERSION, 0x88, 0x90])),
+            Err(CodeError::InvalidCount(9))
+        ));
+        // Everything but the cells
+        assert!(matches!(
+            Board::from_code(&code(&[VERSION, 0x88, 0, 0, 0, 0, 0, 0, 0, 0])),
+            Err(CodeError::WrongLength)
+        ));
+    }
+}
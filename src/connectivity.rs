@@ -0,0 +1,147 @@
+//! Connectivity reasoning on partially solved boards.
+//!
+//! Floor cells (`Empty`, `Monster` and `Chest`) must all end up in one corridor system, and
+//! `Unknown` cells might still become floor, so paths may go through them.
+
+use crate::{Board, Cell, Index, BOARD_SIZE};
+
+impl Board {
+    /// Whether all floor cells can still be joined through non-wall cells.
+    pub fn maybe_connected(&self) -> bool {
+        let Some(start) = self.floor_cells().next() else {
+            return true;
+        };
+
+        let mut seen = [[false; BOARD_SIZE]; BOARD_SIZE];
+        seen[start.0 as usize][start.1 as usize] = true;
+        let mut to_check = vec![start];
+        while let Some((r, c)) = to_check.pop() {
+            for (r, c) in [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)] {
+                if matches!(self.at(r, c), Cell::Wall) || seen[r as usize][c as usize] {
+                    continue;
+                }
+                seen[r as usize][c as usize] = true;
+                to_check.push((r, c));
+            }
+        }
+
+        self.floor_cells()
+            .all(|(r, c)| seen[r as usize][c as usize])
+    }
+
+    /// `Unknown` cells that must be floor, because a wall there would cut some floor cells
+    /// off from the others (articulation points of the non-wall cells).
+    ///
+    /// Assumes that [`Board::maybe_connected`] holds.
+    pub fn forced_floor_cells(&self) -> Vec<(Index, Index)> {
+        let Some(start) = self.floor_cells().next() else {
+            return Vec::new();
+        };
+        let mut dfs = ArticulationSearch {
+            board: self,
+            order: [[0; BOARD_SIZE]; BOARD_SIZE],
+            counter: 0,
+            total_floor: self.floor_cells().count() as u32,
+            forced: Vec::new(),
+        };
+        dfs.visit(start);
+        dfs.forced.sort();
+        dfs.forced.dedup();
+        dfs.forced
+    }
+
+    fn floor_cells(&self) -> impl Iterator<Item = (Index, Index)> + '_ {
+        (0..BOARD_SIZE as Index)
+            .flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Empty | Cell::Monster | Cell::Chest))
+    }
+}
+
+// Tarjan's articulation point search over the non-wall cells
+struct ArticulationSearch<'a> {
+    board: &'a Board,
+    // DFS discovery order, starting from 1; 0 means not visited yet
+    order: [[u32; BOARD_SIZE]; BOARD_SIZE],
+    counter: u32,
+    total_floor: u32,
+    forced: Vec<(Index, Index)>,
+}
+
+impl ArticulationSearch<'_> {
+    // Returns the lowest discovery order reachable from the subtree of (r, c) through one
+    // back edge, and the number of floor cells in that subtree.
+    fn visit(&mut self, (r, c): (Index, Index)) -> (u32, u32) {
+        self.counter += 1;
+        let order = self.counter;
+        self.order[r as usize][c as usize] = order;
+        let cell = self.board.at(r, c);
+        let mut low = order;
+        let mut floor = !matches!(cell, Cell::Unknown) as u32;
+
+        for (nr, nc) in [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)] {
+            if matches!(self.board.at(nr, nc), Cell::Wall) {
+                continue;
+            }
+            let seen = self.order[nr as usize][nc as usize];
+            if seen != 0 {
+                low = low.min(seen);
+                continue;
+            }
+
+            let (child_low, child_floor) = self.visit((nr, nc));
+            low = low.min(child_low);
+            floor += child_floor;
+            // The child's subtree only hangs onto the rest through (r, c). The root has no
+            // "rest" above it, but its subtrees are separated from each other all the same.
+            let cut_off = child_low >= order;
+            if matches!(cell, Cell::Unknown)
+                && cut_off
+                && child_floor > 0
+                && child_floor < self.total_floor
+            {
+                self.forced.push((r, c));
+            }
+        }
+
+        (low, floor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two floor cells joined by unknown cells: a single path, apart from a loop at the top
+    // and a dead end at the bottom
+    fn board() -> Board {
+        Board::from_string(
+            " ????????\n?.  #####\n?#  #####\n?## #####\n?##   . #\n\
+             ?########\n?########\n?########\n?########",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn floor_cells_may_connect_through_unknown_cells() {
+        let mut board = board();
+        assert!(board.maybe_connected());
+        board.cells[2][2] = Cell::Wall;
+        assert!(!board.maybe_connected());
+    }
+
+    #[test]
+    fn articulation_points_are_forced_floor() {
+        assert_eq!(
+            board().forced_floor_cells(),
+            [(0, 1), (1, 2), (2, 2), (3, 2), (3, 3), (3, 4)]
+        );
+    }
+
+    #[test]
+    fn cells_cutting_off_only_unknown_cells_are_not_forced() {
+        let mut board = board();
+        // With a single floor cell left, a wall anywhere only cuts off unknown cells
+        board.cells[3][5] = Cell::Unknown;
+        assert_eq!(board.forced_floor_cells(), []);
+    }
+}
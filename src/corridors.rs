@@ -0,0 +1,107 @@
+//! Corridor width reasoning on partially solved boards.
+//!
+//! Outside of treasure rooms, no 2x2 block may be all floor. On a partial board we do not
+//! know the rooms yet, so a block is only exempt if some chest could still have a room that
+//! covers it.
+
+use crate::{treasure_room_candidates, Board, Cell, Index, BOARD_SIZE};
+
+impl Board {
+    /// Top-left corner of a fully empty 2x2 block that no possible treasure room can cover.
+    pub fn wide_corridor(&self) -> Option<(Index, Index)> {
+        self.uncovered_blocks()
+            .find(|block| {
+                block
+                    .iter()
+                    .all(|&(r, c)| matches!(self.at(r, c), Cell::Empty))
+            })
+            .map(|block| block[0])
+    }
+
+    /// `Unknown` cells that must be walls, because they complete a 2x2 block of floor that no
+    /// possible treasure room can cover.
+    pub fn forced_walls(&self) -> Vec<(Index, Index)> {
+        let mut forced = self
+            .uncovered_blocks()
+            .filter_map(|block| {
+                let empty = block
+                    .iter()
+                    .filter(|&&(r, c)| matches!(self.at(r, c), Cell::Empty))
+                    .count();
+                let unknown = block
+                    .iter()
+                    .find(|&&(r, c)| matches!(self.at(r, c), Cell::Unknown));
+                if empty == 3 {
+                    unknown.copied()
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        forced.sort();
+        forced.dedup();
+        forced
+    }
+
+    // 2x2 blocks, as their four cells starting with the top-left one, that are not exempt
+    // from the corridor width rule by any room the chests can still be in.
+    fn uncovered_blocks(&self) -> impl Iterator<Item = [(Index, Index); 4]> {
+        let possible_rooms = (0..BOARD_SIZE as Index)
+            .flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Chest))
+            .flat_map(|(r, c)| treasure_room_candidates(r, c))
+            .filter(|&(r, c)| self.maybe_treasure_room(r, c))
+            .collect::<Vec<_>>();
+        let checks = self.corridor_width_checks(&possible_rooms);
+
+        (0..BOARD_SIZE as Index - 1)
+            .flat_map(|i| (0..BOARD_SIZE as Index - 1).map(move |j| (i, j)))
+            .filter(move |&(i, j)| checks[i as usize][j as usize])
+            .map(|(i, j)| [(i, j), (i, j + 1), (i + 1, j), (i + 1, j + 1)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An unknown board with three cells of the block at (1, 1) empty, and a chest if given
+    fn board(chest: Option<(usize, usize)>) -> Board {
+        let mut board = Board::from_string(
+            " ????????\n?        \n? ..     \n? .      \n?        \n\
+             ?        \n?        \n?        \n?        ",
+        )
+        .unwrap();
+        if let Some((r, c)) = chest {
+            board.cells[r][c] = Cell::Chest;
+        }
+        board
+    }
+
+    #[test]
+    fn finds_wide_corridors() {
+        let mut board = board(None);
+        assert_eq!(board.wide_corridor(), None);
+        board.cells[2][2] = Cell::Empty;
+        assert_eq!(board.wide_corridor(), Some((1, 1)));
+    }
+
+    #[test]
+    fn the_last_cell_of_a_block_must_be_a_wall() {
+        assert_eq!(board(None).forced_walls(), [(2, 2)]);
+    }
+
+    #[test]
+    fn blocks_a_room_could_cover_are_exempt() {
+        let mut near = board(Some((3, 3)));
+        assert_eq!(near.forced_walls(), []);
+        near.cells[2][2] = Cell::Empty;
+        assert_eq!(near.wide_corridor(), None);
+
+        // Too far for its room to reach the block
+        let mut far = board(Some((6, 6)));
+        assert_eq!(far.forced_walls(), [(2, 2)]);
+        far.cells[2][2] = Cell::Empty;
+        assert_eq!(far.wide_corridor(), Some((1, 1)));
+    }
+}
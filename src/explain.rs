@@ -0,0 +1,155 @@
+//! Explaining why a partially filled board can no longer be solved.
+
+use crate::{
+    search::{BudgetExceeded, NodeBudget, Search, SolveOptions},
+    zobrist::TranspositionTable,
+    Board, Cell, Index, BOARD_SIZE,
+};
+
+const TABLE_SIZE: usize = 1 << 16;
+
+impl Board {
+    /// Finds a small set of placed `Wall`/`Empty` cells that cannot all be part of a solution.
+    ///
+    /// Returns `None` if the board can still be solved. The returned set is irreducible:
+    /// clearing any one of its cells (and every placement not in the set) makes the board
+    /// solvable again. An empty set means the puzzle has no solution at all.
+    pub fn explain_conflict(&self) -> Option<Vec<(Index, Index)>> {
+        self.explain_conflict_within(&mut NodeBudget(u64::MAX))
+            .expect("an unlimited budget never runs out")
+    }
+
+    /// Like [`Board::explain_conflict`], but gives up once its searches have used up
+    /// `budget`.
+    pub fn explain_conflict_within(
+        &self,
+        budget: &mut NodeBudget,
+    ) -> Result<Option<Vec<(Index, Index)>>, BudgetExceeded> {
+        let mut search = Search::new(self.clone(), SolveOptions::default());
+        if search.next_solution_within(budget)?.is_some() {
+            return Ok(None);
+        }
+
+        let placements = (0..BOARD_SIZE)
+            .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
+            .filter(|&(r, c)| {
+                matches!(self.cells[r][c], Cell::Wall | Cell::Empty) && !self.givens[r][c]
+            })
+            .collect::<Vec<_>>();
+
+        let mut bare = self.clone();
+        for &(r, c) in &placements {
+            bare.cells[r][c] = Cell::Unknown;
+        }
+
+        // Deletion filter: drop every placement the contradiction does not depend on. The
+        // boards tried only differ in a few cells, so they share a transposition table.
+        let mut table = TranspositionTable::new(TABLE_SIZE);
+        let mut core = placements;
+        let mut i = 0;
+        while i < core.len() {
+            let mut without = bare.clone();
+            for (k, &(r, c)) in core.iter().enumerate() {
+                if k != i {
+                    without.cells[r][c] = self.cells[r][c];
+                }
+            }
+            let mut search = Search::with_table(without, SolveOptions::default(), table);
+            let solvable = search.next_solution_within(budget)?.is_some();
+            table = search.into_table().unwrap();
+            if solvable {
+                i += 1;
+            } else {
+                core.remove(i);
+            }
+        }
+
+        Ok(Some(
+            core.into_iter()
+                .map(|(r, c)| (r as Index, c as Index))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Last Call BBS 5-8, with some of its column counts hidden
+    fn puzzle(column_counts: &str) -> Board {
+        Board::from_string(&format!(
+            " {column_counts}\n4M   M M \n4        \n2M       \n4       M\n\
+             6M       \n2       M\n3        \n4 M   M M"
+        ))
+        .unwrap()
+    }
+
+    fn placed(board: &Board, cells: &[((usize, usize), Cell)]) -> Board {
+        let mut board = board.clone();
+        for &((r, c), cell) in cells {
+            board.cells[r][c] = cell;
+            board.givens[r][c] = false;
+        }
+        board
+    }
+
+    #[test]
+    fn solvable_boards_have_no_conflict() {
+        let puzzle = puzzle("35344253");
+        assert_eq!(puzzle.explain_conflict(), None);
+        // Both are right
+        let board = placed(&puzzle, &[((0, 1), Cell::Wall), ((1, 0), Cell::Empty)]);
+        assert_eq!(board.explain_conflict(), None);
+    }
+
+    #[test]
+    fn conflicts_leave_out_placements_that_are_right() {
+        let puzzle = puzzle("35344253");
+        // The solution has a wall at (0, 1)
+        let board = placed(
+            &puzzle,
+            &[
+                ((1, 0), Cell::Empty),
+                ((0, 1), Cell::Empty),
+                ((2, 2), Cell::Empty),
+            ],
+        );
+        assert_eq!(board.explain_conflict(), Some(vec![(0, 1)]));
+    }
+
+    #[test]
+    fn conflicts_are_irreducible() {
+        // Two solutions: a cell from each, where they differ, cannot both be right
+        let puzzle = puzzle("???44253");
+        let mut search = Search::new(puzzle.clone(), SolveOptions::default());
+        let first = search.next_solution().unwrap().clone();
+        let second = search.next_solution().unwrap().clone();
+        assert!(search.next_solution().is_none());
+        let differences = (0..BOARD_SIZE)
+            .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
+            .filter(|&(r, c)| first.cells[r][c] != second.cells[r][c])
+            .collect::<Vec<_>>();
+        let (a, b) = (differences[0], differences[differences.len() - 1]);
+        let board = placed(
+            &puzzle,
+            &[(a, first.cells[a.0][a.1]), (b, second.cells[b.0][b.1])],
+        );
+
+        let core = board.explain_conflict().unwrap();
+        let as_index = |(r, c): (usize, usize)| (r as Index, c as Index);
+        assert_eq!(core, [as_index(a), as_index(b)]);
+        for cell in [a, b] {
+            let mut fewer = board.clone();
+            fewer.cells[cell.0][cell.1] = Cell::Unknown;
+            assert_eq!(fewer.explain_conflict(), None);
+        }
+    }
+
+    #[test]
+    fn unsolvable_puzzles_have_an_empty_conflict() {
+        let mut puzzle = puzzle("35344253");
+        puzzle.row_counts[0] = Some(8);
+        assert_eq!(puzzle.explain_conflict(), Some(vec![]));
+    }
+}
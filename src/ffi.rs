@@ -0,0 +1,631 @@
+//! C API, for embedding the solver in programs written in other languages.
+//!
+//! The header is `include/dnd_solver.h`, generated from this file with
+//! `cbindgen --config cbindgen.toml --output include/dnd_solver.h`; `c/test.c` shows how
+//! it is used.
+//!
+//! Boards are opaque handles created by `dnd_board_parse` or `dnd_board_from_code` and
+//! released with `dnd_board_free`. Functions that fail return a null pointer or a status
+//! other than `DND_STATUS_OK`, and leave a message for `dnd_last_error` and the status
+//! for `dnd_last_status`. A panic inside
+//! the library never unwinds into the caller: it fails with `DND_STATUS_INTERNAL` instead.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{
+    format::parse_puzzles,
+    generate::{GenerateOptions, MAX_ROOMS},
+    search::{NodeBudget, Search, SolveOptions, Step, ValueOrder, VariableOrder},
+    Board, BoardError, Cell, Index, BOARD_SIZE,
+};
+
+/// A puzzle or a (partially) solved board.
+pub struct DndBoard(Board);
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DndStatus {
+    Ok = 0,
+    NullArgument,
+    InvalidPuzzle,
+    InvalidOption,
+    OutOfBounds,
+    Unsolvable,
+    BudgetExceeded,
+    /// The board breaks a rule or is incomplete; see `dnd_last_error` for which.
+    NotSolved,
+    /// A bug in the library; see `dnd_last_error`.
+    Internal,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DndCell {
+    Unknown = 0,
+    Empty,
+    Wall,
+    Monster,
+    Chest,
+}
+
+/// Which rule a board breaks, as reported by `dnd_board_check_error`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DndErrorKind {
+    None = 0,
+    Unsolved,
+    WrongRowCount,
+    WrongColumnCount,
+    MonsterNotInDeadEnd,
+    DeadEndWithNoMonster,
+    NoTreasureRoomForChest,
+    CorridorsTooWide,
+    UnconnectedCorridors,
+}
+
+/// A broken rule and where; `row` and `col` are -1 where they do not apply.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct DndBoardError {
+    pub kind: DndErrorKind,
+    pub row: i32,
+    pub col: i32,
+}
+
+/// Options for `dnd_board_solve`; start from `dnd_solve_options_default`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct DndSolveOptions {
+    /// 0 reading order, 1 most constrained, 2 near clues, 3 least slack.
+    pub variable_order: u32,
+    /// 0 wall first, 1 empty first, 2 remaining count.
+    pub value_order: u32,
+    /// Search nodes to give up after, 0 for no limit.
+    pub node_budget: u64,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<(DndStatus, CString)>> = const { RefCell::new(None) };
+}
+
+fn fail(status: DndStatus, message: impl Into<String>) -> DndStatus {
+    let message = CString::new(message.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some((status, message)));
+    status
+}
+
+// What an exported function returns when its body panics.
+trait OnPanic {
+    fn on_panic() -> Self;
+}
+
+impl OnPanic for DndStatus {
+    fn on_panic() -> Self {
+        DndStatus::Internal
+    }
+}
+
+impl<T> OnPanic for *mut T {
+    fn on_panic() -> Self {
+        ptr::null_mut()
+    }
+}
+
+impl<T> OnPanic for *const T {
+    fn on_panic() -> Self {
+        ptr::null()
+    }
+}
+
+impl OnPanic for () {
+    fn on_panic() -> Self {}
+}
+
+impl OnPanic for DndSolveOptions {
+    fn on_panic() -> Self {
+        dnd_solve_options_default()
+    }
+}
+
+// Runs the body of an exported function, failing with `DndStatus::Internal` if it panics
+// rather than unwinding into the caller.
+fn guard<T: OnPanic>(body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => payload
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_default(),
+        };
+        fail(DndStatus::Internal, format!("internal error: {message}"));
+        T::on_panic()
+    })
+}
+
+// The string behind `text`, or the status to fail with.
+unsafe fn string_arg<'a>(text: *const c_char) -> Result<&'a str, DndStatus> {
+    if text.is_null() {
+        return Err(fail(DndStatus::NullArgument, "unexpected null pointer"));
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|_| fail(DndStatus::InvalidPuzzle, "the text is not UTF-8"))
+}
+
+/// The version of the library, as a static string.
+#[no_mangle]
+pub extern "C" fn dnd_version() -> *const c_char {
+    guard(|| concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char)
+}
+
+/// The message left by the last failed call on this thread, or null. It stays valid until
+/// the next failure.
+#[no_mangle]
+pub extern "C" fn dnd_last_error() -> *const c_char {
+    guard(|| {
+        LAST_ERROR.with(|e| {
+            e.borrow()
+                .as_ref()
+                .map_or(ptr::null(), |(_, message)| message.as_ptr())
+        })
+    })
+}
+
+/// The status of the last failure on this thread, or `DND_STATUS_OK` if nothing failed
+/// yet. Useful after a function that returns a null pointer.
+#[no_mangle]
+pub extern "C" fn dnd_last_status() -> DndStatus {
+    guard(|| {
+        LAST_ERROR.with(|e| {
+            e.borrow()
+                .as_ref()
+                .map_or(DndStatus::Ok, |&(status, _)| status)
+        })
+    })
+}
+
+/// Parses a puzzle in the text format, either a bare grid or a file holding one puzzle.
+///
+/// # Safety
+///
+/// `text` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_parse(text: *const c_char) -> *mut DndBoard {
+    guard(|| {
+        let Ok(text) = string_arg(text) else {
+            return ptr::null_mut();
+        };
+        match parse_puzzles(text) {
+            Ok(mut puzzles) if puzzles.len() == 1 => {
+                Box::into_raw(Box::new(DndBoard(puzzles.pop().unwrap().board)))
+            }
+            Ok(puzzles) => {
+                let message = format!("expected one puzzle, got {}", puzzles.len());
+                fail(DndStatus::InvalidPuzzle, message);
+                ptr::null_mut()
+            }
+            Err(e) => {
+                fail(DndStatus::InvalidPuzzle, format!("invalid puzzle: {e:?}"));
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Decodes a puzzle code.
+///
+/// # Safety
+///
+/// `code` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_from_code(code: *const c_char) -> *mut DndBoard {
+    guard(|| {
+        let Ok(code) = string_arg(code) else {
+            return ptr::null_mut();
+        };
+        match Board::from_code(code) {
+            Ok(board) => Box::into_raw(Box::new(DndBoard(board))),
+            Err(e) => {
+                fail(
+                    DndStatus::InvalidPuzzle,
+                    format!("invalid puzzle code: {e:?}"),
+                );
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// A copy of the board, released separately.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_clone(board: *const DndBoard) -> *mut DndBoard {
+    guard(|| match board.as_ref() {
+        Some(DndBoard(board)) => Box::into_raw(Box::new(DndBoard(board.clone()))),
+        None => {
+            fail(DndStatus::NullArgument, "unexpected null pointer");
+            ptr::null_mut()
+        }
+    })
+}
+
+/// Releases a board; null is ignored.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, which must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_free(board: *mut DndBoard) {
+    guard(|| {
+        if !board.is_null() {
+            drop(Box::from_raw(board));
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn dnd_solve_options_default() -> DndSolveOptions {
+    guard(|| DndSolveOptions {
+        variable_order: 0,
+        value_order: 0,
+        node_budget: 0,
+    })
+}
+
+/// Replaces the board with its first solution. The board is left alone on failure.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, and `options` null (for the defaults) or valid.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_solve(
+    board: *mut DndBoard,
+    options: *const DndSolveOptions,
+) -> DndStatus {
+    guard(|| {
+        let Some(DndBoard(board)) = board.as_mut() else {
+            return fail(DndStatus::NullArgument, "unexpected null pointer");
+        };
+        let options = options
+            .as_ref()
+            .copied()
+            .unwrap_or_else(|| dnd_solve_options_default());
+        let (Some(&variable_order), Some(&value_order)) = (
+            VariableOrder::ALL.get(options.variable_order as usize),
+            ValueOrder::ALL.get(options.value_order as usize),
+        ) else {
+            return fail(DndStatus::InvalidOption, "unknown variable or value order");
+        };
+
+        let solve_options = SolveOptions {
+            variable_order,
+            value_order,
+        };
+        let mut search = Search::new(board.clone(), solve_options);
+        if let Err(status) = run(&mut search, options.node_budget, |step| {
+            step == Step::Solution
+        }) {
+            return status;
+        }
+        if search.is_exhausted() {
+            return fail(DndStatus::Unsolvable, "the puzzle has no solution");
+        }
+        *board = search.board().clone();
+        board.reveal_counts();
+        DndStatus::Ok
+    })
+}
+
+/// Counts the solutions of the board, stopping at `limit`, and writes the count to `count`.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, and `count` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_count_solutions(
+    board: *const DndBoard,
+    limit: u64,
+    node_budget: u64,
+    count: *mut u64,
+) -> DndStatus {
+    guard(|| {
+        let (Some(DndBoard(board)), Some(count)) = (board.as_ref(), count.as_mut()) else {
+            return fail(DndStatus::NullArgument, "unexpected null pointer");
+        };
+        let mut search = Search::new(board.clone(), SolveOptions::default());
+        let mut solutions = 0;
+        if let Err(status) = run(&mut search, node_budget, |step| {
+            solutions += (step == Step::Solution) as u64;
+            solutions >= limit
+        }) {
+            return status;
+        }
+        *count = solutions;
+        DndStatus::Ok
+    })
+}
+
+// Steps through the search until `stop` says so or it is exhausted, within `node_budget`
+// nodes if that is not 0.
+fn run(
+    search: &mut Search,
+    node_budget: u64,
+    mut stop: impl FnMut(Step) -> bool,
+) -> Result<(), DndStatus> {
+    let mut budget = budget(node_budget);
+    loop {
+        let step = search
+            .step_within(&mut budget)
+            .map_err(|_| over_budget(node_budget))?;
+        if step == Step::Exhausted || stop(step) {
+            return Ok(());
+        }
+    }
+}
+
+// A budget of `node_budget` nodes, where 0 means no limit.
+fn budget(node_budget: u64) -> NodeBudget {
+    match node_budget {
+        0 => NodeBudget(u64::MAX),
+        n => NodeBudget(n),
+    }
+}
+
+fn over_budget(node_budget: u64) -> DndStatus {
+    let message = format!("gave up after {node_budget} search nodes");
+    fail(DndStatus::BudgetExceeded, message)
+}
+
+/// Generates a puzzle with a unique solution; the same seed always gives the same puzzle.
+///
+/// Gives up after `node_budget` search nodes, or never if it is 0. Unless `solution` is
+/// null, it receives a handle to the solution, to be freed like the puzzle.
+///
+/// # Safety
+///
+/// `solution` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_generate(
+    seed: u64,
+    rooms: u32,
+    hide_counts: bool,
+    node_budget: u64,
+    solution: *mut *mut DndBoard,
+) -> *mut DndBoard {
+    guard(|| {
+        if rooms as usize > MAX_ROOMS {
+            fail(
+                DndStatus::InvalidOption,
+                format!("at most {MAX_ROOMS} rooms are supported"),
+            );
+            return ptr::null_mut();
+        }
+        let options = GenerateOptions {
+            seed,
+            rooms: rooms as usize,
+            hide_counts,
+        };
+        match Board::generate_within(&options, &mut budget(node_budget)) {
+            Ok(generated) => {
+                if let Some(solution) = solution.as_mut() {
+                    *solution = Box::into_raw(Box::new(DndBoard(generated.solution)));
+                }
+                Box::into_raw(Box::new(DndBoard(generated.puzzle)))
+            }
+            Err(_) => {
+                over_budget(node_budget);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Whether the board is a complete and valid solution.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_check(board: *const DndBoard) -> DndStatus {
+    guard(|| {
+        let Some(DndBoard(board)) = board.as_ref() else {
+            return fail(DndStatus::NullArgument, "unexpected null pointer");
+        };
+        match board.check_solved() {
+            Ok(()) => DndStatus::Ok,
+            Err(e) => fail(DndStatus::NotSolved, format!("{e:?}")),
+        }
+    })
+}
+
+/// Like `dnd_board_check`, but also writes the broken rule to `error`.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, and `error` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_check_error(
+    board: *const DndBoard,
+    error: *mut DndBoardError,
+) -> DndStatus {
+    guard(|| {
+        let Some(error) = error.as_mut() else {
+            return fail(DndStatus::NullArgument, "unexpected null pointer");
+        };
+        let status = dnd_board_check(board);
+        *error = match board.as_ref().map(|DndBoard(board)| board.check_solved()) {
+            None | Some(Ok(())) => DndBoardError {
+                kind: DndErrorKind::None,
+                row: -1,
+                col: -1,
+            },
+            Some(Err(e)) => board_error(e),
+        };
+        status
+    })
+}
+
+/// Writes every rule the board breaks to `errors`, up to `capacity` of them, and their
+/// total number to `count`. A board with `Unknown` cells only breaks `Unsolved`.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, `errors` valid for `capacity` writes (or null if
+/// `capacity` is 0), and `count` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_violations(
+    board: *const DndBoard,
+    errors: *mut DndBoardError,
+    capacity: usize,
+    count: *mut usize,
+) -> DndStatus {
+    guard(|| {
+        let (Some(DndBoard(board)), Some(count)) = (board.as_ref(), count.as_mut()) else {
+            return fail(DndStatus::NullArgument, "unexpected null pointer");
+        };
+        if errors.is_null() && capacity > 0 {
+            return fail(DndStatus::NullArgument, "unexpected null pointer");
+        }
+        let violations = board.violations();
+        for (i, violation) in violations.iter().take(capacity).enumerate() {
+            *errors.add(i) = board_error(violation.error);
+        }
+        *count = violations.len();
+        DndStatus::Ok
+    })
+}
+
+fn board_error(error: BoardError) -> DndBoardError {
+    let (kind, row, col): (_, Index, Index) = match error {
+        BoardError::Unsolved => (DndErrorKind::Unsolved, -1, -1),
+        BoardError::WrongRowCount(r) => (DndErrorKind::WrongRowCount, r, -1),
+        BoardError::WrongColumnCount(c) => (DndErrorKind::WrongColumnCount, -1, c),
+        BoardError::MonsterNotInDeadEnd(r, c) => (DndErrorKind::MonsterNotInDeadEnd, r, c),
+        BoardError::DeadEndWithNoMontster(r, c) => (DndErrorKind::DeadEndWithNoMonster, r, c),
+        BoardError::NoTreasureRoomForChest(r, c) => (DndErrorKind::NoTreasureRoomForChest, r, c),
+        BoardError::CorridorsTooWide(r, c) => (DndErrorKind::CorridorsTooWide, r, c),
+        BoardError::UnconnectedCorridors => (DndErrorKind::UnconnectedCorridors, -1, -1),
+    };
+    DndBoardError {
+        kind,
+        row: row.into(),
+        col: col.into(),
+    }
+}
+
+/// Writes the count of row `i` to `count`, or -1 if the puzzle hides it.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, and `count` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_row_count(
+    board: *const DndBoard,
+    i: i32,
+    count: *mut i32,
+) -> DndStatus {
+    guard(|| line_count(board, i, count, |board| &board.row_counts))
+}
+
+/// Writes the count of column `i` to `count`, or -1 if the puzzle hides it.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, and `count` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_column_count(
+    board: *const DndBoard,
+    i: i32,
+    count: *mut i32,
+) -> DndStatus {
+    guard(|| line_count(board, i, count, |board| &board.column_counts))
+}
+
+unsafe fn line_count(
+    board: *const DndBoard,
+    i: i32,
+    count: *mut i32,
+    counts: impl Fn(&Board) -> &[Option<u8>; BOARD_SIZE],
+) -> DndStatus {
+    let (Some(DndBoard(board)), Some(count)) = (board.as_ref(), count.as_mut()) else {
+        return fail(DndStatus::NullArgument, "unexpected null pointer");
+    };
+    let Some(&n) = usize::try_from(i).ok().and_then(|i| counts(board).get(i)) else {
+        return fail(DndStatus::OutOfBounds, "no such line");
+    };
+    *count = n.map_or(-1, i32::from);
+    DndStatus::Ok
+}
+
+/// Writes the cell at (`row`, `col`) to `cell`.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle, and `cell` null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_cell(
+    board: *const DndBoard,
+    row: i32,
+    col: i32,
+    cell: *mut DndCell,
+) -> DndStatus {
+    guard(|| {
+        let (Some(DndBoard(board)), Some(cell)) = (board.as_ref(), cell.as_mut()) else {
+            return fail(DndStatus::NullArgument, "unexpected null pointer");
+        };
+        let (Ok(row), Ok(col)) = (Index::try_from(row), Index::try_from(col)) else {
+            return fail(DndStatus::OutOfBounds, "no such cell");
+        };
+        if !board.is_in_bounds(row, col) {
+            return fail(DndStatus::OutOfBounds, "no such cell");
+        }
+        *cell = match board.at(row, col) {
+            Cell::Unknown => DndCell::Unknown,
+            Cell::Empty => DndCell::Empty,
+            Cell::Wall => DndCell::Wall,
+            Cell::Monster => DndCell::Monster,
+            Cell::Chest => DndCell::Chest,
+        };
+        DndStatus::Ok
+    })
+}
+
+/// The board in the text format, to be released with `dnd_string_free`.
+///
+/// # Safety
+///
+/// `board` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_board_to_string(board: *const DndBoard) -> *mut c_char {
+    guard(|| {
+        let Some(DndBoard(board)) = board.as_ref() else {
+            fail(DndStatus::NullArgument, "unexpected null pointer");
+            return ptr::null_mut();
+        };
+        // The text format has no NUL bytes
+        CString::new(board.to_string()).unwrap().into_raw()
+    })
+}
+
+/// Releases a string returned by the library; null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string from `dnd_board_to_string`, not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_string_free(s: *mut c_char) {
+    guard(|| {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    })
+}
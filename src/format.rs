@@ -0,0 +1,290 @@
+//! Puzzle files: one or more grids, each with an optional metadata header.
+//!
+//! ```text
+//! puzzles 1
+//! # Comments start with `#` in the first column.
+//! title: Lair of the Elder Wyrm
+//! source: Last Call BBS 5-8
+//!  35344253
+//! ...
+//!
+//! title: The next puzzle
+//! ...
+//! ```
+//!
+//! The `puzzles 1` version line is optional, so a bare grid as read by
+//! [`Board::from_string`] is a valid file. Puzzles are separated by blank lines; the
+//! header keys are `title`, `author`, `source`, `difficulty` and `notes`, the last of
+//! which may be repeated to add lines to the notes. Values are kept as written, apart
+//! from the one space after the colon.
+
+use std::fmt;
+
+use crate::{Board, BOARD_SIZE};
+
+const VERSION: &str = "1";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub source: Option<String>,
+    pub difficulty: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle {
+    pub metadata: Metadata,
+    pub board: Board,
+}
+
+// Line numbers are 1-based.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FormatError {
+    UnsupportedVersion(String),
+    UnknownKey(usize, String),
+    DuplicateKey(usize, String),
+    // A header without a grid after it
+    MissingGrid(usize),
+    // The grid starting at this line is malformed
+    InvalidGrid(usize),
+    // Neither a comment, a header entry nor the start of a grid
+    InvalidLine(usize),
+    // A metadata value with a line break, which cannot be written; notes may have `\n`
+    InvalidValue(String),
+}
+
+impl Metadata {
+    fn field(&mut self, key: &str) -> Option<&mut Option<String>> {
+        Some(match key {
+            "title" => &mut self.title,
+            "author" => &mut self.author,
+            "source" => &mut self.source,
+            "difficulty" => &mut self.difficulty,
+            "notes" => &mut self.notes,
+            _ => return None,
+        })
+    }
+
+    // Whether every value fits in the header lines written for it.
+    fn check(&self) -> Result<(), FormatError> {
+        let fields = [
+            ("title", &self.title),
+            ("author", &self.author),
+            ("source", &self.source),
+            ("difficulty", &self.difficulty),
+            ("notes", &self.notes),
+        ];
+        for (key, value) in fields {
+            let breaks = |c| c == '\r' || (c == '\n' && key != "notes");
+            if value.as_deref().is_some_and(|value| value.contains(breaks)) {
+                return Err(FormatError::InvalidValue(key.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Puzzle {
+    pub fn new(board: Board) -> Puzzle {
+        Puzzle {
+            metadata: Metadata::default(),
+            board,
+        }
+    }
+}
+
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = [
+            ("title", &self.title),
+            ("author", &self.author),
+            ("source", &self.source),
+            ("difficulty", &self.difficulty),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                writeln!(f, "{key}: {value}")?;
+            }
+        }
+        if let Some(notes) = &self.notes {
+            for line in notes.split('\n') {
+                writeln!(f, "notes: {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.metadata, self.board)
+    }
+}
+
+/// Parses every puzzle in a file, in order.
+pub fn parse_puzzles(spec: &str) -> Result<Vec<Puzzle>, FormatError> {
+    let mut lines = spec
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .peekable();
+    let mut puzzles = Vec::new();
+    let mut metadata = Metadata::default();
+    let mut header_start = None;
+    let mut first = true;
+
+    while let Some((n, line)) = lines.next() {
+        if line.starts_with('#') {
+            continue;
+        }
+        if first && !line.is_empty() {
+            first = false;
+            if let Some(version) = line.strip_prefix("puzzles ") {
+                if version != VERSION {
+                    return Err(FormatError::UnsupportedVersion(version.to_string()));
+                }
+                continue;
+            }
+        }
+
+        if line.is_empty() {
+            if let Some(start) = header_start {
+                return Err(FormatError::MissingGrid(start));
+            }
+        } else if line.starts_with(' ') {
+            // Column counts: the grid is this line and the next `BOARD_SIZE`
+            let mut grid = vec![line];
+            while grid.len() < BOARD_SIZE + 1 {
+                match lines.next_if(|(_, line)| !line.is_empty() && !line.starts_with('#')) {
+                    Some((_, line)) => grid.push(line),
+                    None => return Err(FormatError::InvalidGrid(n)),
+                }
+            }
+            let board =
+                Board::from_string(&grid.join("\n")).map_err(|_| FormatError::InvalidGrid(n))?;
+            puzzles.push(Puzzle {
+                metadata: std::mem::take(&mut metadata),
+                board,
+            });
+            header_start = None;
+        } else {
+            let (key, value) = line.split_once(':').ok_or(FormatError::InvalidLine(n))?;
+            let field = metadata
+                .field(key)
+                .ok_or_else(|| FormatError::UnknownKey(n, key.to_string()))?;
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                Some(notes) if key == "notes" => {
+                    notes.push('\n');
+                    notes.push_str(value);
+                }
+                Some(_) => return Err(FormatError::DuplicateKey(n, key.to_string())),
+                None => *field = Some(value.to_string()),
+            }
+            header_start.get_or_insert(n);
+        }
+    }
+    if let Some(start) = header_start {
+        return Err(FormatError::MissingGrid(start));
+    }
+
+    Ok(puzzles)
+}
+
+/// Writes puzzles in the format read by [`parse_puzzles`], which reads them back as they
+/// were. Fails on metadata values with line breaks, other than in the notes.
+pub fn write_puzzles(puzzles: &[Puzzle]) -> Result<String, FormatError> {
+    let mut out = format!("puzzles {VERSION}\n");
+    for (i, puzzle) in puzzles.iter().enumerate() {
+        puzzle.metadata.check()?;
+        if i > 0 {
+            out.push('\n');
+        }
+        out += &puzzle.to_string();
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID: &str = " 35344253\n4M   M M \n4        \n2M       \n4       M\n\
+                        6M       \n2       M\n3        \n4 M   M M\n";
+
+    fn board() -> Board {
+        Board::from_string(GRID).unwrap()
+    }
+
+    #[test]
+    fn puzzles_round_trip() {
+        let mut first = Puzzle::new(board());
+        first.metadata = Metadata {
+            title: Some("  Lair of the Elder Wyrm ".to_string()),
+            author: Some(String::new()),
+            source: Some("Last Call BBS 5-8".to_string()),
+            difficulty: None,
+            notes: Some("First line\n\n  indented: with a colon ".to_string()),
+        };
+        let mut solved = board();
+        solved.solve().unwrap();
+        let puzzles = vec![first, Puzzle::new(solved)];
+
+        let written = write_puzzles(&puzzles).unwrap();
+        assert!(written.starts_with("puzzles 1\n"));
+        assert_eq!(parse_puzzles(&written).unwrap(), puzzles);
+    }
+
+    #[test]
+    fn reads_bare_grids_and_comments() {
+        let puzzles = parse_puzzles(&format!("# A comment\ntitle: x\n{GRID}\n{GRID}")).unwrap();
+        assert_eq!(puzzles.len(), 2);
+        assert_eq!(puzzles[0].metadata.title.as_deref(), Some("x"));
+        assert_eq!(puzzles[1], Puzzle::new(board()));
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        assert_eq!(
+            parse_puzzles(&format!("puzzles 2\n{GRID}")),
+            Err(FormatError::UnsupportedVersion("2".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_metadata() {
+        let parse = |header: &str| parse_puzzles(&format!("puzzles 1\n{header}{GRID}"));
+        assert_eq!(
+            parse("title: a\nrating: 5\n"),
+            Err(FormatError::UnknownKey(3, "rating".to_string()))
+        );
+        assert_eq!(
+            parse("title: a\ntitle: b\n"),
+            Err(FormatError::DuplicateKey(3, "title".to_string()))
+        );
+        assert_eq!(parse("no colon\n"), Err(FormatError::InvalidLine(2)));
+        assert_eq!(
+            parse_puzzles("title: a\n\n"),
+            Err(FormatError::MissingGrid(1))
+        );
+        assert_eq!(
+            parse_puzzles("title: a\n 35344253\n"),
+            Err(FormatError::InvalidGrid(2))
+        );
+
+        let mut puzzle = Puzzle::new(board());
+        puzzle.metadata.title = Some("two\nlines".to_string());
+        assert_eq!(
+            write_puzzles(&[puzzle]),
+            Err(FormatError::InvalidValue("title".to_string()))
+        );
+    }
+
+    #[test]
+    fn grids_start_with_a_space() {
+        assert!(Board::from_string(&GRID.replacen(' ', "?", 1)).is_err());
+        assert!(Board::from_string(&GRID.replacen(' ', "3", 1)).is_err());
+    }
+}
@@ -0,0 +1,500 @@
+//! Game state on top of a [`Board`]: player moves with undo/redo.
+//!
+//! History is kept as a tree, so making a move after undoing starts a new
+//! branch instead of throwing the undone moves away. Each node of the tree is one
+//! step: usually a single move, but a reset clears every cell in one step.
+//!
+//! Moves carry a timestamp from the caller; the game never reads the clock itself, since
+//! not every target has one.
+
+use crate::{Board, Cell, Index, ParseError, BOARD_SIZE};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub row: Index,
+    pub col: Index,
+    pub old: Cell,
+    pub new: Cell,
+    /// When the move was made, as passed in by the caller: usually milliseconds since the
+    /// Unix epoch.
+    pub timestamp: u64,
+}
+
+#[derive(Debug)]
+pub enum MoveError {
+    OutOfBounds,
+    // Givens, including every monster and chest, are part of the puzzle
+    FixedCell,
+    // Players can only place `Wall`, `Empty` or clear a cell back to `Unknown`
+    InvalidValue,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    parent: usize,
+    // Applied in order, and undone in reverse
+    moves: Vec<Move>,
+    children: Vec<usize>,
+    // Child taken by `redo`: the one most recently played or undone
+    selected: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Game {
+    initial: Board,
+    board: Board,
+    // `nodes[0]` is the starting position and has no moves
+    nodes: Vec<Node>,
+    current: usize,
+}
+
+impl Game {
+    pub fn new(board: Board) -> Game {
+        let root = Node {
+            parent: 0,
+            moves: Vec::new(),
+            children: Vec::new(),
+            selected: None,
+        };
+        Game {
+            initial: board.clone(),
+            board,
+            nodes: vec![root],
+            current: 0,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn play(
+        &mut self,
+        row: Index,
+        col: Index,
+        new: Cell,
+        timestamp: u64,
+    ) -> Result<(), MoveError> {
+        let old = check_move(&self.board, row, col, new)?;
+        if old != new {
+            self.step(vec![Move {
+                row,
+                col,
+                old,
+                new,
+                timestamp,
+            }]);
+        }
+        Ok(())
+    }
+
+    /// Clears every placed cell in a single step, so that one undo brings them all back.
+    pub fn reset_to_puzzle(&mut self, timestamp: u64) {
+        let mut moves = Vec::new();
+        for row in 0..BOARD_SIZE as Index {
+            for col in 0..BOARD_SIZE as Index {
+                if let Ok(old @ (Cell::Empty | Cell::Wall)) =
+                    check_move(&self.board, row, col, Cell::Unknown)
+                {
+                    moves.push(Move {
+                        row,
+                        col,
+                        old,
+                        new: Cell::Unknown,
+                        timestamp,
+                    });
+                }
+            }
+        }
+        if !moves.is_empty() {
+            self.step(moves);
+        }
+    }
+
+    // Makes `moves` as one step from the current position.
+    fn step(&mut self, moves: Vec<Move>) {
+        // Replaying a step that is already in the history just follows that branch
+        let same = |other: &[Move]| {
+            other.len() == moves.len()
+                && other
+                    .iter()
+                    .zip(&moves)
+                    .all(|(a, b)| (a.row, a.col, a.new) == (b.row, b.col, b.new))
+        };
+        let existing = self.nodes[self.current]
+            .children
+            .iter()
+            .copied()
+            .find(|&n| same(&self.nodes[n].moves));
+        let next = existing.unwrap_or_else(|| {
+            self.nodes.push(Node {
+                parent: self.current,
+                moves,
+                children: Vec::new(),
+                selected: None,
+            });
+            let n = self.nodes.len() - 1;
+            self.nodes[self.current].children.push(n);
+            n
+        });
+        self.nodes[self.current].selected = Some(next);
+        self.enter(next);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current != 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.nodes[self.current].selected.is_some()
+    }
+
+    /// Undoes the last step, returning its moves.
+    pub fn undo(&mut self) -> Option<Vec<Move>> {
+        if !self.can_undo() {
+            return None;
+        }
+        let node = &self.nodes[self.current];
+        for mv in node.moves.iter().rev() {
+            self.board.cells[mv.row as usize][mv.col as usize] = mv.old;
+        }
+        let parent = node.parent;
+        let moves = node.moves.clone();
+        self.nodes[parent].selected = Some(self.current);
+        self.current = parent;
+        Some(moves)
+    }
+
+    pub fn redo(&mut self) -> Option<Vec<Move>> {
+        let next = self.nodes[self.current].selected?;
+        self.enter(next);
+        Some(self.nodes[next].moves.clone())
+    }
+
+    /// Steps that can be redone from the current position, oldest branch first.
+    pub fn branches(&self) -> Vec<Vec<Move>> {
+        self.nodes[self.current]
+            .children
+            .iter()
+            .map(|&n| self.nodes[n].moves.clone())
+            .collect()
+    }
+
+    /// Redoes the `i`-th step returned by [`Game::branches`].
+    pub fn redo_branch(&mut self, i: usize) -> Option<Vec<Move>> {
+        let next = *self.nodes[self.current].children.get(i)?;
+        self.nodes[self.current].selected = Some(next);
+        self.redo()
+    }
+
+    /// Moves leading from the starting position to the current one.
+    pub fn history(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut n = self.current;
+        while n != 0 {
+            moves.extend(self.nodes[n].moves.iter().rev());
+            n = self.nodes[n].parent;
+        }
+        moves.reverse();
+        moves
+    }
+
+    fn enter(&mut self, n: usize) {
+        for mv in &self.nodes[n].moves {
+            self.board.cells[mv.row as usize][mv.col as usize] = mv.new;
+        }
+        self.current = n;
+    }
+
+    /// Serializes the starting board and the whole history tree, one step per line.
+    pub fn save(&self) -> String {
+        let mut out = format!("game 1\ncurrent {}\n{}", self.current, self.initial);
+        for node in &self.nodes[1..] {
+            out += &node.parent.to_string();
+            for mv in &node.moves {
+                out += &format!(
+                    " {} {} {} {} {}",
+                    mv.row,
+                    mv.col,
+                    mv.old.name(),
+                    mv.new.name(),
+                    mv.timestamp
+                );
+            }
+            out += "\n";
+        }
+        out
+    }
+
+    /// Loads a saved game, checking every step as [`Game::play`] would.
+    pub fn load(saved: &str) -> Result<Game, ParseError> {
+        let mut lines = saved.lines();
+        if lines.next() != Some("game 1") {
+            return Err(ParseError);
+        }
+        let current = lines
+            .next()
+            .and_then(|l| l.strip_prefix("current "))
+            .and_then(|n| n.parse::<usize>().ok())
+            .ok_or(ParseError)?;
+        let grid = lines.by_ref().take(BOARD_SIZE + 1).collect::<Vec<_>>();
+        let mut game = Game::new(Board::from_string(&grid.join("\n"))?);
+
+        // The board after each step, to check the moves of its children against
+        let mut boards = vec![game.board.clone()];
+        for line in lines {
+            let fields = line.split(' ').collect::<Vec<_>>();
+            let (parent, fields) = fields.split_first().ok_or(ParseError)?;
+            let parent = parent.parse::<usize>().map_err(|_| ParseError)?;
+            if parent >= game.nodes.len() || fields.is_empty() || fields.len() % 5 != 0 {
+                return Err(ParseError);
+            }
+            let mut board = boards[parent].clone();
+            let mut moves = Vec::new();
+            for fields in fields.chunks(5) {
+                let [row, col, old, new, timestamp] = fields[..] else {
+                    unreachable!()
+                };
+                let mv = Move {
+                    row: row.parse().map_err(|_| ParseError)?,
+                    col: col.parse().map_err(|_| ParseError)?,
+                    old: Cell::from_name(old).ok_or(ParseError)?,
+                    new: Cell::from_name(new).ok_or(ParseError)?,
+                    timestamp: timestamp.parse().map_err(|_| ParseError)?,
+                };
+                let old = check_move(&board, mv.row, mv.col, mv.new).map_err(|_| ParseError)?;
+                if old != mv.old || old == mv.new {
+                    return Err(ParseError);
+                }
+                board.cells[mv.row as usize][mv.col as usize] = mv.new;
+                moves.push(mv);
+            }
+            game.nodes.push(Node {
+                parent,
+                moves,
+                children: Vec::new(),
+                selected: None,
+            });
+            boards.push(board);
+            let n = game.nodes.len() - 1;
+            game.nodes[parent].children.push(n);
+            game.nodes[parent].selected = Some(n);
+        }
+        if current >= game.nodes.len() {
+            return Err(ParseError);
+        }
+
+        // Replay the path to the current position
+        let mut path = Vec::new();
+        let mut n = current;
+        while n != 0 {
+            path.push(n);
+            n = game.nodes[n].parent;
+        }
+        for &n in path.iter().rev() {
+            let parent = game.nodes[n].parent;
+            game.nodes[parent].selected = Some(n);
+            game.enter(n);
+        }
+
+        Ok(game)
+    }
+}
+
+// Checks that the player may set the cell to `new`, returning its current value.
+fn check_move(board: &Board, row: Index, col: Index, new: Cell) -> Result<Cell, MoveError> {
+    if !board.is_in_bounds(row, col) {
+        return Err(MoveError::OutOfBounds);
+    }
+    let old = board.at(row, col);
+    if board.is_given(row, col) || matches!(old, Cell::Monster | Cell::Chest) {
+        return Err(MoveError::FixedCell);
+    }
+    if !matches!(new, Cell::Unknown | Cell::Empty | Cell::Wall) {
+        return Err(MoveError::InvalidValue);
+    }
+    Ok(old)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Last Call BBS 5-8, with a given wall at (6, 0)
+    fn game() -> Game {
+        Game::new(
+            Board::from_string(
+                " 35344253\n4M   M M \n4        \n2M       \n4       M\n\
+                 6M       \n2       M\n3#       \n4 M   M M",
+            )
+            .unwrap(),
+        )
+    }
+
+    fn cell(game: &Game, row: Index, col: Index) -> Cell {
+        game.board().at(row, col)
+    }
+
+    #[test]
+    fn rejects_moves_the_player_cannot_make() {
+        let mut game = game();
+        assert!(matches!(
+            game.play(0, 0, Cell::Wall, 0),
+            Err(MoveError::FixedCell)
+        ));
+        assert!(matches!(
+            game.play(6, 0, Cell::Empty, 0),
+            Err(MoveError::FixedCell)
+        ));
+        assert!(matches!(
+            game.play(1, 1, Cell::Monster, 0),
+            Err(MoveError::InvalidValue)
+        ));
+        assert!(matches!(
+            game.play(8, 0, Cell::Wall, 0),
+            Err(MoveError::OutOfBounds)
+        ));
+        // Setting a cell to what it already is makes no step
+        game.play(1, 1, Cell::Unknown, 0).unwrap();
+        assert!(!game.can_undo());
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_history() {
+        let mut game = game();
+        game.play(1, 1, Cell::Wall, 10).unwrap();
+        game.play(1, 2, Cell::Empty, 20).unwrap();
+        assert_eq!(game.history().len(), 2);
+
+        let undone = game.undo().unwrap();
+        assert_eq!(
+            undone,
+            [Move {
+                row: 1,
+                col: 2,
+                old: Cell::Unknown,
+                new: Cell::Empty,
+                timestamp: 20,
+            }]
+        );
+        assert_eq!(cell(&game, 1, 2), Cell::Unknown);
+        game.undo().unwrap();
+        assert_eq!(cell(&game, 1, 1), Cell::Unknown);
+        assert!(!game.can_undo());
+        assert_eq!(game.undo(), None);
+
+        game.redo().unwrap();
+        game.redo().unwrap();
+        assert_eq!(cell(&game, 1, 1), Cell::Wall);
+        assert_eq!(cell(&game, 1, 2), Cell::Empty);
+        assert!(!game.can_redo());
+        assert_eq!(game.redo(), None);
+    }
+
+    #[test]
+    fn a_move_after_undo_starts_a_branch() {
+        let mut game = game();
+        game.play(1, 1, Cell::Wall, 0).unwrap();
+        game.undo();
+        game.play(1, 1, Cell::Empty, 0).unwrap();
+        game.undo();
+
+        let branches = game.branches();
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0][0].new, Cell::Wall);
+        assert_eq!(branches[1][0].new, Cell::Empty);
+
+        // `redo` follows the branch last played or undone
+        game.redo();
+        assert_eq!(cell(&game, 1, 1), Cell::Empty);
+        game.undo();
+        game.redo_branch(0);
+        assert_eq!(cell(&game, 1, 1), Cell::Wall);
+        game.undo();
+        game.redo();
+        assert_eq!(cell(&game, 1, 1), Cell::Wall);
+        assert_eq!(game.redo_branch(2), None);
+    }
+
+    #[test]
+    fn replaying_a_step_follows_its_branch() {
+        let mut game = game();
+        game.play(1, 1, Cell::Wall, 0).unwrap();
+        game.undo();
+        game.play(1, 1, Cell::Wall, 5).unwrap();
+        assert_eq!(game.history().len(), 1);
+        game.undo();
+        assert_eq!(game.branches().len(), 1);
+        // The step keeps the time it was first made
+        assert_eq!(game.branches()[0][0].timestamp, 0);
+    }
+
+    #[test]
+    fn a_reset_is_undone_in_one_step() {
+        let mut game = game();
+        game.play(1, 1, Cell::Wall, 0).unwrap();
+        game.play(1, 2, Cell::Empty, 0).unwrap();
+        game.reset_to_puzzle(0);
+        assert_eq!(game.board(), &game.board().puzzle());
+        assert_eq!(game.history().len(), 4);
+
+        assert_eq!(game.undo().unwrap().len(), 2);
+        assert_eq!(cell(&game, 1, 1), Cell::Wall);
+        assert_eq!(cell(&game, 1, 2), Cell::Empty);
+
+        // Nothing to clear is no step at all
+        let mut fresh = self::game();
+        fresh.reset_to_puzzle(0);
+        assert!(!fresh.can_undo());
+    }
+
+    #[test]
+    fn save_and_load_keep_the_whole_tree() {
+        let mut game = game();
+        game.play(1, 1, Cell::Wall, 1).unwrap();
+        game.play(1, 2, Cell::Empty, 2).unwrap();
+        game.undo();
+        game.play(1, 2, Cell::Wall, 3).unwrap();
+        game.reset_to_puzzle(4);
+        game.undo();
+
+        let saved = game.save();
+        let loaded = Game::load(&saved).unwrap();
+        assert_eq!(loaded.save(), saved);
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.history(), game.history());
+        let (mut game, mut loaded) = (game, loaded);
+        game.undo();
+        loaded.undo();
+        assert_eq!(loaded.branches(), game.branches());
+        assert_eq!(loaded.redo(), game.redo());
+    }
+
+    #[test]
+    fn load_rejects_moves_play_would_reject() {
+        let mut game = game();
+        game.play(1, 1, Cell::Wall, 0).unwrap();
+        let saved = game.save();
+        assert!(saved.ends_with("\n0 1 1 unknown wall 0\n"));
+        let with_step = |step: &str| saved.replace("\n0 1 1 unknown wall 0\n", step);
+
+        let tampered = [
+            // Given cells, monsters and chests
+            with_step("\n0 6 0 wall empty 0\n"),
+            with_step("\n0 0 0 monster wall 0\n"),
+            // A value players cannot place
+            with_step("\n0 1 1 unknown chest 0\n"),
+            // An old value that does not match the board, or no change at all
+            with_step("\n0 1 1 empty wall 0\n"),
+            with_step("\n0 1 1 unknown unknown 0\n"),
+            // Out of bounds, or a parent that comes later
+            with_step("\n0 8 1 unknown wall 0\n"),
+            with_step("\n1 1 1 unknown wall 0\n"),
+            saved.replace("current 1", "current 2"),
+            saved.replace("game 1", "game 2"),
+        ];
+        for tampered in tampered {
+            assert!(Game::load(&tampered).is_err(), "{tampered}");
+        }
+    }
+}
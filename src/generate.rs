@@ -0,0 +1,246 @@
+//! Generating random puzzles with a unique solution.
+
+use crate::{
+    minimize::{MinimizeError, MinimizeOptions},
+    rng::SplitMix64,
+    room_inside_coords, room_outside_coords,
+    search::{BudgetExceeded, NodeBudget, Search},
+    Board, Cell, Index, BOARD_SIZE,
+};
+
+/// More treasure rooms than this rarely fit on the board together with a maze.
+pub const MAX_ROOMS: usize = 2;
+
+#[derive(Copy, Clone, Debug)]
+pub struct GenerateOptions {
+    /// The same seed always gives the same puzzle.
+    pub seed: u64,
+    /// Number of treasure rooms, up to [`MAX_ROOMS`].
+    pub rooms: usize,
+    /// Also hide the row and column counts that are not needed.
+    pub hide_counts: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Generated {
+    pub puzzle: Board,
+    pub solution: Board,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            seed: 0,
+            rooms: 1,
+            hide_counts: false,
+        }
+    }
+}
+
+impl Board {
+    /// Carves a random dungeon and turns it into a puzzle with a unique solution.
+    ///
+    /// The puzzle starts out as the monsters, chests and counts of the dungeon; cells of
+    /// the solution are added as givens while other solutions exist, and whatever is not
+    /// needed in the end is removed again with [`Board::minimize`].
+    pub fn generate(options: &GenerateOptions) -> Generated {
+        Board::generate_within(options, &mut NodeBudget(u64::MAX))
+            .expect("an unlimited budget never runs out")
+    }
+
+    /// Like [`Board::generate`], but gives up once its searches have used up `budget`.
+    pub fn generate_within(
+        options: &GenerateOptions,
+        budget: &mut NodeBudget,
+    ) -> Result<Generated, BudgetExceeded> {
+        let mut rng = SplitMix64(options.seed);
+        let solution = loop {
+            // Each attempt at carving a dungeon costs a node, so that unlucky seeds give up too
+            if budget.0 == 0 {
+                return Err(BudgetExceeded);
+            }
+            budget.0 -= 1;
+            if let Some(dungeon) = carve(&mut rng, options.rooms.min(MAX_ROOMS)) {
+                break dungeon;
+            }
+        };
+
+        let mut puzzle = solution.clone();
+        for (cell, given) in puzzle
+            .cells
+            .iter_mut()
+            .flatten()
+            .zip(puzzle.givens.iter_mut().flatten())
+        {
+            if matches!(cell, Cell::Wall | Cell::Empty) {
+                *cell = Cell::Unknown;
+                *given = false;
+            }
+        }
+        loop {
+            let mut search = Search::new(puzzle.clone(), Default::default());
+            let other = loop {
+                match search.next_solution_within(budget)? {
+                    Some(other) if other.cells == solution.cells => {}
+                    other => break other.cloned(),
+                }
+            };
+            let Some(other) = other else {
+                break;
+            };
+            let differences = (0..BOARD_SIZE)
+                .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
+                .filter(|&(r, c)| other.cells[r][c] != solution.cells[r][c])
+                .collect::<Vec<_>>();
+            let (r, c) = differences[rng.below(differences.len())];
+            puzzle.cells[r][c] = solution.cells[r][c];
+            puzzle.givens[r][c] = true;
+        }
+
+        let minimized = puzzle.minimize_within(
+            &MinimizeOptions {
+                counts: options.hide_counts,
+            },
+            budget,
+        );
+        match minimized {
+            Ok(minimized) => Ok(Generated {
+                puzzle: minimized.puzzle,
+                solution,
+            }),
+            Err(MinimizeError::BudgetExceeded) => Err(BudgetExceeded),
+            Err(_) => unreachable!("generated puzzles have a unique solution"),
+        }
+    }
+}
+
+// A random solved board, or `None` if this attempt did not work out.
+fn carve(rng: &mut SplitMix64, rooms: usize) -> Option<Board> {
+    let mut board = Board {
+        cells: [[Cell::Wall; BOARD_SIZE]; BOARD_SIZE],
+        column_counts: [None; BOARD_SIZE],
+        row_counts: [None; BOARD_SIZE],
+        givens: [[true; BOARD_SIZE]; BOARD_SIZE],
+    };
+    // Cells of the rooms and the walls around them, which the maze must leave alone
+    let mut reserved = [[false; BOARD_SIZE]; BOARD_SIZE];
+    let mut frontier = Vec::new();
+
+    for _ in 0..rooms {
+        let last = (BOARD_SIZE - 3) as Index;
+        let (top, left) = (
+            rng.below(last as usize + 1) as Index,
+            rng.below(last as usize + 1) as Index,
+        );
+        let inside = room_inside_coords(top, left);
+        let outside = room_outside_coords(top, left)
+            .into_iter()
+            .filter(|&(r, c)| board.is_in_bounds(r, c))
+            .collect::<Vec<_>>();
+        if inside
+            .into_iter()
+            .chain(outside.iter().copied())
+            .any(|(r, c)| reserved[r as usize][c as usize])
+        {
+            return None;
+        }
+        for (r, c) in inside {
+            board.cells[r as usize][c as usize] = Cell::Empty;
+        }
+        let (r, c) = inside[rng.below(inside.len())];
+        board.cells[r as usize][c as usize] = Cell::Chest;
+        for &(r, c) in inside.iter().chain(&outside) {
+            reserved[r as usize][c as usize] = true;
+        }
+
+        // The entrance leads straight out of the room, onto a cell the maze can grow from
+        let exits = outside
+            .into_iter()
+            .filter_map(|(r, c)| {
+                let (dr, dc) = (r - top - 1, c - left - 1);
+                let step = (dr.signum() * (dr.abs() / 2), dc.signum() * (dc.abs() / 2));
+                let (nr, nc) = (r + step.0, c + step.1);
+                (board.is_in_bounds(nr, nc) && !reserved[nr as usize][nc as usize])
+                    .then_some(((r, c), (nr, nc)))
+            })
+            .collect::<Vec<_>>();
+        if exits.is_empty() {
+            return None;
+        }
+        let ((r, c), (nr, nc)) = exits[rng.below(exits.len())];
+        board.cells[r as usize][c as usize] = Cell::Empty;
+        board.cells[nr as usize][nc as usize] = Cell::Empty;
+        frontier.push((nr, nc));
+    }
+    if frontier.is_empty() {
+        let (r, c) = (
+            rng.below(BOARD_SIZE) as Index,
+            rng.below(BOARD_SIZE) as Index,
+        );
+        board.cells[r as usize][c as usize] = Cell::Empty;
+        frontier.push((r, c));
+    }
+
+    // Grow the maze from random cells at its border, without ever making it two cells wide
+    while !frontier.is_empty() {
+        let i = rng.below(frontier.len());
+        let (r, c) = frontier[i];
+        let next = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)]
+            .into_iter()
+            .filter(|&(r, c)| {
+                board.is_in_bounds(r, c)
+                    && !reserved[r as usize][c as usize]
+                    && matches!(board.at(r, c), Cell::Wall)
+                    && !opens_block(&board, r, c)
+            })
+            .collect::<Vec<_>>();
+        if next.is_empty() {
+            frontier.swap_remove(i);
+            continue;
+        }
+        let (r, c) = next[rng.below(next.len())];
+        board.cells[r as usize][c as usize] = Cell::Empty;
+        frontier.push((r, c));
+    }
+
+    for r in 0..BOARD_SIZE as Index {
+        for c in 0..BOARD_SIZE as Index {
+            if !reserved[r as usize][c as usize] && board.is_dead_end(r, c) {
+                board.cells[r as usize][c as usize] = Cell::Monster;
+            }
+        }
+    }
+    board.reveal_counts();
+    board.check_solved().is_ok().then_some(board)
+}
+
+// Whether turning the wall at (r, c) into floor completes a 2x2 block of floor.
+fn opens_block(board: &Board, r: Index, c: Index) -> bool {
+    [(-1, -1), (-1, 0), (0, -1), (0, 0)]
+        .into_iter()
+        .any(|(dr, dc)| {
+            let (top, left) = (r + dr, c + dc);
+            [(0, 0), (0, 1), (1, 0), (1, 1)].into_iter().all(|(i, j)| {
+                let (br, bc) = (top + i, left + j);
+                (br, bc) == (r, c)
+                    || (board.is_in_bounds(br, bc) && !matches!(board.at(br, bc), Cell::Wall))
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn carving_is_charged_to_the_budget() {
+        let options = GenerateOptions::default();
+        assert!(Board::generate_within(&options, &mut NodeBudget(0)).is_err());
+
+        let mut budget = NodeBudget(u64::MAX);
+        let generated = Board::generate_within(&options, &mut budget).unwrap();
+        assert!(budget.0 < u64::MAX);
+        assert!(generated.solution.check_solved().is_ok());
+        assert_eq!(generated.puzzle.count_solutions(2), 1);
+    }
+}
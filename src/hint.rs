@@ -0,0 +1,145 @@
+//! Hints for a player stuck on a partially filled board.
+
+use crate::{
+    search::{BudgetExceeded, NodeBudget, Search, SolveOptions, VariableOrder},
+    Board, Cell, Index,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    /// The only way to fill the row or column with the right number of walls.
+    Line,
+    /// A wall here would cut the corridors in two.
+    Connectivity,
+    /// Floor here would make a corridor two cells wide.
+    Corridor,
+    /// No simple deduction is left; the value comes from the only solution.
+    Solution,
+    /// No simple deduction is left and the board has more than one solution; the value
+    /// comes from one of them, and others may differ here.
+    Guess,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// A cell whose value can be filled in next.
+    Deduction {
+        row: Index,
+        col: Index,
+        value: Cell,
+        reason: Reason,
+    },
+    /// The board cannot be solved anymore. These placed cells cannot all be right; it is
+    /// empty if the puzzle itself has no solution.
+    Conflict(Vec<(Index, Index)>),
+}
+
+impl Board {
+    /// The next step towards the solution, or `None` if the board is already solved.
+    ///
+    /// Deductions are preferred in the order of [`Reason`], since those are the easiest to
+    /// follow.
+    pub fn hint(&self) -> Option<Hint> {
+        self.hint_within(&mut NodeBudget(u64::MAX))
+            .expect("an unlimited budget never runs out")
+    }
+
+    /// Like [`Board::hint`], but gives up once its searches have used up `budget`.
+    pub fn hint_within(&self, budget: &mut NodeBudget) -> Result<Option<Hint>, BudgetExceeded> {
+        if self.check_solved().is_ok() {
+            return Ok(None);
+        }
+        if let Some(cells) = self.explain_conflict_within(budget)? {
+            return Ok(Some(Hint::Conflict(cells)));
+        }
+
+        let deduction = |((row, col), value), reason| Hint::Deduction {
+            row,
+            col,
+            value,
+            reason,
+        };
+        // Cannot fail: the board can still be solved
+        if let Some(&forced) = self.line_deductions().unwrap_or_default().first() {
+            return Ok(Some(deduction(forced, Reason::Line)));
+        }
+        if let Some(&rc) = self.forced_floor_cells().first() {
+            return Ok(Some(deduction((rc, Cell::Empty), Reason::Connectivity)));
+        }
+        if let Some(&rc) = self.forced_walls().first() {
+            return Ok(Some(deduction((rc, Cell::Wall), Reason::Corridor)));
+        }
+
+        let mut search = Search::new(self.clone(), SolveOptions::default());
+        let Some(solution) = search.next_solution_within(budget)?.cloned() else {
+            return Ok(None);
+        };
+        let reason = match search.next_solution_within(budget)? {
+            Some(_) => Reason::Guess,
+            None => Reason::Solution,
+        };
+        let Some((r, c)) = self.branching_cell(VariableOrder::MostConstrained) else {
+            return Ok(None);
+        };
+        let value = solution.at(r, c);
+        Ok(Some(deduction(((r, c), value), reason)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Last Call BBS 5-8, with some of its column counts hidden
+    fn puzzle(column_counts: &str) -> Board {
+        Board::from_string(&format!(
+            " {column_counts}\n4M   M M \n4        \n2M       \n4       M\n\
+             6M       \n2       M\n3        \n4 M   M M"
+        ))
+        .unwrap()
+    }
+
+    // The reasons of the hints that lead from `board` to a solution.
+    fn follow_hints(mut board: Board) -> Vec<Reason> {
+        let mut reasons = Vec::new();
+        while let Some(hint) = board.hint() {
+            let Hint::Deduction {
+                row,
+                col,
+                value,
+                reason,
+            } = hint
+            else {
+                panic!("a hint led to a conflict: {hint:?}");
+            };
+            board.cells[row as usize][col as usize] = value;
+            reasons.push(reason);
+        }
+        assert!(board.check_solved().is_ok());
+        reasons
+    }
+
+    #[test]
+    fn hints_solve_a_unique_puzzle_without_guessing() {
+        let reasons = follow_hints(puzzle("35344253"));
+        assert!(!reasons.contains(&Reason::Guess));
+    }
+
+    #[test]
+    fn hints_say_when_they_pick_one_of_several_solutions() {
+        let board = puzzle("???44253");
+        assert_eq!(board.count_solutions(3), 2);
+        assert!(follow_hints(board).contains(&Reason::Guess));
+    }
+
+    #[test]
+    fn conflicts_point_at_placed_cells() {
+        let mut board = puzzle("35344253");
+        // The solution has a wall here
+        board.cells[0][1] = Cell::Empty;
+        match board.hint() {
+            Some(Hint::Conflict(cells)) => assert!(cells.contains(&(0, 1))),
+            hint => panic!("expected a conflict, got {hint:?}"),
+        }
+    }
+}
@@ -0,0 +1,638 @@
+use std::fmt;
+
+use search::{SolveOptions, SolveStats};
+
+pub mod api;
+pub mod bench;
+pub mod certificate;
+pub mod code;
+pub mod connectivity;
+pub mod corridors;
+pub mod explain;
+pub mod ffi;
+pub mod format;
+pub mod game;
+pub mod generate;
+pub mod hint;
+pub mod json;
+pub mod lines;
+pub mod minimize;
+pub mod propagate;
+pub mod rng;
+pub mod rooms;
+mod sat;
+pub mod search;
+pub mod server;
+pub mod solver;
+pub mod symmetry;
+pub mod violations;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+pub mod zobrist;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Cell {
+    Unknown,
+    Empty,
+    Wall,
+    Monster,
+    Chest,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    // At least one cell is `Unsolved`
+    Unsolved, // TODO do we need coordinates?
+    WrongRowCount(Index),
+    WrongColumnCount(Index),
+    MonsterNotInDeadEnd(Index, Index),
+    DeadEndWithNoMontster(Index, Index),
+    NoTreasureRoomForChest(Index, Index),
+    CorridorsTooWide(Index, Index),
+    UnconnectedCorridors,
+}
+
+#[derive(Debug)]
+pub struct ParseError; // TODO distinguish errors (but nobody actually cares)
+
+pub type Index = i8;
+pub const BOARD_SIZE: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Board {
+    pub cells: [[Cell; BOARD_SIZE]; BOARD_SIZE],
+    // `None` for counts hidden by the puzzle
+    pub column_counts: [Option<u8>; BOARD_SIZE],
+    pub row_counts: [Option<u8>; BOARD_SIZE],
+    // Cells printed in the puzzle, as opposed to filled in by the player or the solver
+    pub givens: [[bool; BOARD_SIZE]; BOARD_SIZE],
+}
+
+#[derive(Debug)]
+pub struct Unsolvable;
+
+impl Cell {
+    // Lowercase variant name, used by the line-based save formats.
+    pub fn name(self) -> &'static str {
+        match self {
+            Cell::Unknown => "unknown",
+            Cell::Empty => "empty",
+            Cell::Wall => "wall",
+            Cell::Monster => "monster",
+            Cell::Chest => "chest",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Cell> {
+        Some(match name {
+            "unknown" => Cell::Unknown,
+            "empty" => Cell::Empty,
+            "wall" => Cell::Wall,
+            "monster" => Cell::Monster,
+            "chest" => Cell::Chest,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            " {}",
+            self.column_counts
+                .into_iter()
+                .map(count_char)
+                .collect::<String>()
+        )?;
+        for i in 0..BOARD_SIZE {
+            writeln!(
+                f,
+                "{}{}",
+                count_char(self.row_counts[i]),
+                self.cells[i]
+                    .into_iter()
+                    .zip(self.givens[i])
+                    .map(|(cell, given)| match (cell, given) {
+                        (Cell::Unknown, _) => ' ',
+                        (Cell::Empty, true) => '.',
+                        (Cell::Empty, false) => ',',
+                        (Cell::Wall, true) => '#',
+                        (Cell::Wall, false) => 'x',
+                        (Cell::Monster, _) => 'M',
+                        (Cell::Chest, _) => 'C',
+                    })
+                    .collect::<String>()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Board {
+    pub fn from_string(spec: &str) -> Result<Board, ParseError> {
+        let mut lines = spec.lines().map(|s| s.as_bytes());
+        let first_line = lines.next().ok_or(ParseError {})?;
+        if first_line.len() != BOARD_SIZE + 1 || first_line[0] != b' ' {
+            return Err(ParseError {});
+        }
+        let mut column_counts = [None; BOARD_SIZE];
+        for i in 0..BOARD_SIZE {
+            column_counts[i] = parse_count(first_line[i + 1])?;
+        }
+        let mut row_counts = [None; BOARD_SIZE];
+        let mut cells = [[Cell::Empty; BOARD_SIZE]; BOARD_SIZE];
+        let mut givens = [[true; BOARD_SIZE]; BOARD_SIZE];
+        let mut rows = 0;
+        for (i, line) in lines.enumerate() {
+            rows += 1;
+            if i >= BOARD_SIZE || line.len() != BOARD_SIZE + 1 {
+                return Err(ParseError {});
+            }
+            row_counts[i] = parse_count(line[0])?;
+            for j in 0..BOARD_SIZE {
+                (cells[i][j], givens[i][j]) = match line[j + 1] {
+                    b' ' => (Cell::Unknown, false),
+                    b'.' => (Cell::Empty, true),
+                    b',' => (Cell::Empty, false),
+                    b'#' => (Cell::Wall, true),
+                    b'x' => (Cell::Wall, false),
+                    b'M' => (Cell::Monster, true),
+                    b'C' => (Cell::Chest, true),
+                    _ => return Err(ParseError {}),
+                }
+            }
+        }
+        if rows != BOARD_SIZE {
+            return Err(ParseError {});
+        }
+
+        Ok(Board {
+            cells,
+            column_counts,
+            row_counts,
+            givens,
+        })
+    }
+
+    pub fn is_given(&self, r: Index, c: Index) -> bool {
+        self.is_in_bounds(r, c) && self.givens[r as usize][c as usize]
+    }
+
+    /// Clears every cell that is not a given.
+    pub fn reset_to_puzzle(&mut self) {
+        for (cell, &given) in self
+            .cells
+            .iter_mut()
+            .flatten()
+            .zip(self.givens.iter().flatten())
+        {
+            if !given {
+                *cell = Cell::Unknown;
+            }
+        }
+    }
+
+    /// The board with only its givens.
+    pub fn puzzle(&self) -> Board {
+        let mut puzzle = self.clone();
+        puzzle.reset_to_puzzle();
+        puzzle
+    }
+
+    pub fn rows_acceptable(&self) -> Result<(), Index> {
+        let unknown_counts = self
+            .cells
+            .iter()
+            .map(|row| row.iter().filter(|c| matches!(c, Cell::Unknown)).count());
+        let wall_counts = self
+            .cells
+            .iter()
+            .map(|row| row.iter().filter(|c| matches!(c, Cell::Wall)).count());
+
+        let ranges = wall_counts
+            .zip(unknown_counts)
+            .map(|(walls, unkns)| walls as u8..=(walls + unkns) as u8);
+
+        ranges
+            .zip(self.row_counts)
+            .enumerate()
+            .find_map(|(i, (range, cnt))| cnt.is_some_and(|cnt| !range.contains(&cnt)).then_some(i))
+            .map_or(Ok(()), |i| Err(i as Index))
+    }
+
+    pub fn cols_acceptable(&self) -> Result<(), Index> {
+        let columns = (0..BOARD_SIZE).map(|i| self.cells.iter().map(move |row| row[i]));
+        let wall_counts = columns
+            .clone()
+            .map(|col| col.filter(|c| matches!(c, Cell::Wall)).count() as u8);
+        let unkn_counts =
+            columns.map(|col| col.filter(|c| matches!(c, Cell::Unknown)).count() as u8);
+        let ranges = wall_counts
+            .zip(unkn_counts)
+            .map(|(walls, unkns)| walls..=walls + unkns);
+
+        ranges
+            .zip(self.column_counts)
+            .enumerate()
+            .find_map(|(i, (range, cnt))| cnt.is_some_and(|cnt| !range.contains(&cnt)).then_some(i))
+            .map_or(Ok(()), |i| Err(i as Index))
+    }
+
+    pub fn check_solved(&self) -> Result<(), BoardError> {
+        // * No `Unknown`
+        // * All column & row counts are satisfied
+        // * Monsters are in dead ends; all dead ends have monsters
+        // * All treasure rooms have 3x3 empty space and one entrance
+        // * No 2x2 empty spaces
+        // * All corridors connected
+        use BoardError as E;
+
+        if self
+            .cells
+            .iter()
+            .flatten()
+            .any(|c| matches!(c, Cell::Unknown))
+        {
+            return Err(E::Unsolved);
+        }
+
+        let cur_row_counts = self
+            .cells
+            .iter()
+            .map(|row| row.iter().filter(|c| matches!(c, Cell::Wall)).count() as u8);
+        let bad_row = cur_row_counts
+            .zip(self.row_counts)
+            .enumerate()
+            .find_map(|(i, (a, b))| b.is_some_and(|b| a != b).then_some(i));
+        if let Some(r) = bad_row {
+            return Err(E::WrongRowCount(r as Index));
+        }
+
+        let columns = (0..BOARD_SIZE).map(|i| self.cells.iter().map(move |row| row[i]));
+        let cur_col_counts =
+            columns.map(|col| col.filter(|c| matches!(c, Cell::Wall)).count() as u8);
+        let bad_col = cur_col_counts
+            .zip(self.column_counts)
+            .enumerate()
+            .find_map(|(i, (a, b))| b.is_some_and(|b| a != b).then_some(i));
+        if let Some(c) = bad_col {
+            return Err(E::WrongColumnCount(c as Index));
+        }
+
+        let mut treasure_rooms = Vec::<(Index, Index)>::new();
+
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let is_monster = matches!(self.cells[i][j], Cell::Monster);
+                let is_dead_end = self.is_dead_end(i as Index, j as Index);
+                if is_monster != is_dead_end {
+                    // "if and only if" relation
+                    return if is_monster {
+                        Err(E::MonsterNotInDeadEnd(i as Index, j as Index))
+                    } else {
+                        Err(E::DeadEndWithNoMontster(i as Index, j as Index))
+                    };
+                }
+
+                if let Cell::Chest = self.cells[i][j] {
+                    let maybe_room = treasure_room_candidates(i as Index, j as Index)
+                        .into_iter()
+                        .find(|&(r, c)| self.is_treasure_room(r, c));
+                    match maybe_room {
+                        Some(room) => {
+                            treasure_rooms.push(room);
+                        }
+                        None => {
+                            return Err(E::NoTreasureRoomForChest(i as Index, j as Index));
+                        }
+                    }
+                }
+            }
+        }
+
+        let coords_to_check = self.corridor_width_checks(&treasure_rooms);
+
+        for (i, row) in coords_to_check.iter().enumerate().take(BOARD_SIZE - 1) {
+            for (j, &check) in row.iter().enumerate().take(BOARD_SIZE - 1) {
+                if !check {
+                    continue;
+                }
+                let is_empty_2x2 = [(i, j), (i, j + 1), (i + 1, j), (i + 1, j + 1)]
+                    .into_iter()
+                    .all(|(i, j)| matches!(self.cells[i][j], Cell::Empty));
+                if is_empty_2x2 {
+                    return Err(E::CorridorsTooWide(i as Index, j as Index));
+                }
+            }
+        }
+
+        let first_empty_cell = (0..BOARD_SIZE)
+            .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
+            .find(|&(r, c)| matches!(self.cells[r][c], Cell::Empty))
+            .map(|(r, c)| (r as Index, c as Index));
+        let mut to_check: Vec<(Index, Index)> = first_empty_cell.into_iter().collect();
+        let mut seen = [[false; BOARD_SIZE]; BOARD_SIZE];
+        let mut connected_cells: u32 = 0;
+
+        while let Some((r, c)) = to_check.pop() {
+            let seen_this = &mut seen[r as usize][c as usize];
+            if *seen_this {
+                continue;
+            }
+            *seen_this = true;
+            connected_cells += 1;
+            let neighbors = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)];
+            to_check.extend(
+                // TODO this kinda ugly... `seen` is unelegant & maybe a footgun
+                neighbors
+                    .into_iter()
+                    .filter(|&(r, c)| !matches!(self.at(r, c), Cell::Wall)),
+            )
+        }
+
+        if first_empty_cell.is_none() {
+            return Ok(()); // unlikely, but who knows?
+        }
+
+        let total_empty = self
+            .cells
+            .iter()
+            .flatten()
+            .filter(|&c| !matches!(c, Cell::Wall))
+            .count() as u32;
+
+        // All empty cells are connected
+        if connected_cells != total_empty {
+            return Err(E::UnconnectedCorridors);
+        }
+
+        Ok(())
+    }
+
+    // Top-left corners of the 2x2 blocks that must not be all empty: those that are not
+    // part of (or right next to) one of `treasure_rooms`.
+    pub(crate) fn corridor_width_checks(
+        &self,
+        treasure_rooms: &[(Index, Index)],
+    ) -> [[bool; BOARD_SIZE]; BOARD_SIZE] {
+        let mut check = [[true; BOARD_SIZE]; BOARD_SIZE];
+        for &(r, c) in treasure_rooms {
+            wide_corridor_exemptions(r, c)
+                .filter_map(|(r, c)| self.is_in_bounds(r, c).then_some((r as usize, c as usize)))
+                .for_each(|(r, c)| {
+                    check[r][c] = false;
+                });
+        }
+
+        check
+    }
+
+    // Accepts out-of-bounds coordinates, and assumes there are walls everywhere outside the board.
+    pub fn at(&self, r: Index, c: Index) -> Cell {
+        if self.is_in_bounds(r, c) {
+            self.cells[r as usize][c as usize]
+        } else {
+            Cell::Wall
+        }
+    }
+
+    pub fn is_in_bounds(&self, r: Index, c: Index) -> bool {
+        (0..BOARD_SIZE as Index).contains(&r) && (0..BOARD_SIZE as Index).contains(&c)
+    }
+
+    pub fn is_dead_end(&self, r: Index, c: Index) -> bool {
+        if matches!(self.at(r, c), Cell::Unknown | Cell::Wall) {
+            return false;
+        }
+        let surrounding_wall_count = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)]
+            .into_iter()
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
+            .count();
+
+        surrounding_wall_count == 3
+    }
+
+    pub fn maybe_dead_end(&self, r: Index, c: Index) -> bool {
+        let surrounding_cells = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)];
+        let walls = surrounding_cells
+            .into_iter()
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
+            .count();
+        let air = surrounding_cells
+            .into_iter()
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Empty))
+            .count();
+
+        walls <= 3 && air <= 1
+    }
+
+    pub fn maybe_treasure_room(&self, r: Index, c: Index) -> bool {
+        let inside_coords = room_inside_coords(r, c);
+        let mut chest_seen = false;
+        for (r, c) in inside_coords {
+            match self.at(r, c) {
+                Cell::Chest => {
+                    if chest_seen {
+                        return false;
+                    };
+                    chest_seen = true;
+                }
+                Cell::Empty | Cell::Unknown => {}
+                _ => {
+                    return false;
+                }
+            }
+        }
+        let outside_coords = room_outside_coords(r, c);
+        let wall_count = outside_coords
+            .into_iter()
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
+            .count();
+        let unknown_count = outside_coords
+            .into_iter()
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Unknown))
+            .count();
+
+        (wall_count..=wall_count + unknown_count).contains(&(outside_coords.len() - 1))
+    }
+
+    pub fn is_treasure_room(&self, r: Index, c: Index) -> bool {
+        let inside_coords = room_inside_coords(r, c);
+        let mut chest_seen = false;
+        for (r, c) in inside_coords {
+            match self.at(r, c) {
+                Cell::Chest => {
+                    if chest_seen {
+                        return false;
+                    };
+                    chest_seen = true;
+                }
+                Cell::Empty => {}
+                _ => {
+                    return false;
+                }
+            }
+        }
+        let outside_coords = room_outside_coords(r, c);
+        let wall_count = outside_coords
+            .into_iter()
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
+            .count();
+
+        wall_count == outside_coords.len() - 1
+    }
+
+    pub fn solve(&mut self) -> Result<(), Unsolvable> {
+        self.solve_with(&SolveOptions::default(), &mut SolveStats::default())
+    }
+
+    /// Fills in the hidden row and column counts from the walls on the board.
+    pub fn reveal_counts(&mut self) {
+        for i in 0..BOARD_SIZE {
+            let row_walls = (0..BOARD_SIZE).filter(|&j| matches!(self.cells[i][j], Cell::Wall));
+            let col_walls = (0..BOARD_SIZE).filter(|&j| matches!(self.cells[j][i], Cell::Wall));
+            self.row_counts[i].get_or_insert(row_walls.count() as u8);
+            self.column_counts[i].get_or_insert(col_walls.count() as u8);
+        }
+    }
+
+    pub fn maybe_solvable(&self) -> Result<(), BoardError> {
+        self.lines_acceptable()?;
+        self.cells_acceptable()?;
+
+        if let Some((r, c)) = self.wide_corridor() {
+            return Err(BoardError::CorridorsTooWide(r, c));
+        }
+
+        if !self.maybe_connected() {
+            return Err(BoardError::UnconnectedCorridors);
+        }
+
+        Ok(())
+    }
+
+    /// The checks [`Board::solve`] started out with: the counts are still within reach,
+    /// monsters and dead ends go together, and each chest has room for a treasure room.
+    /// Much weaker than [`Board::maybe_solvable`].
+    pub fn basic_checks(&self) -> Result<(), BoardError> {
+        self.rows_acceptable().map_err(BoardError::WrongRowCount)?;
+        self.cols_acceptable()
+            .map_err(BoardError::WrongColumnCount)?;
+        self.cells_acceptable()
+    }
+
+    // Monsters and dead ends, and rooms for chests.
+    fn cells_acceptable(&self) -> Result<(), BoardError> {
+        for i in 0..BOARD_SIZE {
+            for j in 0..BOARD_SIZE {
+                let is_monster = matches!(self.cells[i][j], Cell::Monster);
+                let (r, c) = (i as Index, j as Index);
+                let maybe_dead_end = self.maybe_dead_end(r, c);
+                let is_dead_end = self.is_dead_end(r, c);
+                if is_monster && !maybe_dead_end {
+                    return Err(BoardError::MonsterNotInDeadEnd(r, c));
+                }
+                if !is_monster && is_dead_end {
+                    return Err(BoardError::DeadEndWithNoMontster(r, c));
+                }
+
+                if let Cell::Chest = self.cells[i][j] {
+                    let maybe_room = treasure_room_candidates(r, c)
+                        .into_iter()
+                        .find(|&(r, c)| self.maybe_treasure_room(r, c));
+                    if maybe_room.is_none() {
+                        return Err(BoardError::NoTreasureRoomForChest(r, c));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn count_char(count: Option<u8>) -> char {
+    count.map_or('?', |n| (b'0' + n) as char)
+}
+
+fn parse_count(ch: u8) -> Result<Option<u8>, ParseError> {
+    match ch {
+        b'?' => Ok(None),
+        b'0'..=b'9' if (ch - b'0') as usize <= BOARD_SIZE => Ok(Some(ch - b'0')),
+        _ => Err(ParseError),
+    }
+}
+
+// Top-left corners of all 3x3 rooms that contain the cell at (r, c).
+pub(crate) fn treasure_room_candidates(r: Index, c: Index) -> [(Index, Index); 9] {
+    [
+        (r - 2, c - 2),
+        (r - 2, c - 1),
+        (r - 2, c),
+        (r - 1, c - 2),
+        (r - 1, c - 1),
+        (r - 1, c),
+        (r, c - 2),
+        (r, c - 1),
+        (r, c),
+    ]
+}
+
+// Top-left corners of the 2x2 blocks that a treasure room with top-left corner at (r, c)
+// allows to be empty, some of which may be outside the board:
+// . # # # # .
+// # # # # # #
+// # # # # # #
+// # # # # # #
+// # # # # # #
+// . # # # # .
+pub(crate) fn wide_corridor_exemptions(r: Index, c: Index) -> impl Iterator<Item = (Index, Index)> {
+    (-1..=2)
+        .map(|c| (-2, c)) // rect (-2, -1) ..= (-2, +2)
+        .chain(
+            // rect (-1, -2) ..= (+2, +3)
+            (-1..=2).flat_map(|r| (-2..=3).map(move |c| (r, c))),
+        )
+        .chain(
+            // rect (+3, -1) ..= (+3, +2)
+            (-1..=2).map(|c| (3, c)),
+        )
+        .map(move |(dr, dc)| (r + dr, c + dc))
+}
+
+// Cells of the 3x3 room with top-left corner at (r, c).
+pub(crate) fn room_inside_coords(r: Index, c: Index) -> [(Index, Index); 9] {
+    [
+        (r, c),
+        (r, c + 1),
+        (r, c + 2),
+        (r + 1, c),
+        (r + 1, c + 1),
+        (r + 1, c + 2),
+        (r + 2, c),
+        (r + 2, c + 1),
+        (r + 2, c + 2),
+    ]
+}
+
+// Cells around the 3x3 room with top-left corner at (r, c), excluding the corners.
+pub(crate) fn room_outside_coords(r: Index, c: Index) -> [(Index, Index); 12] {
+    [
+        // top
+        (r - 1, c),
+        (r - 1, c + 1),
+        (r - 1, c + 2),
+        //left-right
+        (r, c - 1),
+        (r, c + 3),
+        (r + 1, c - 1),
+        (r + 1, c + 3),
+        (r + 2, c - 1),
+        (r + 2, c + 3),
+        // bottom
+        (r + 3, c),
+        (r + 3, c + 1),
+        (r + 3, c + 2),
+    ]
+}
@@ -0,0 +1,249 @@
+//! Nonogram-style line solving for the row and column counts.
+//!
+//! A line is a row or column of cells, and a wall pattern is a bitmask with bit `i` set iff
+//! cell `i` of the line is a wall. A line can be completed by any pattern with the right
+//! number of walls that agrees with the cells decided so far; every `Unknown` cell that has
+//! the same value in all of those patterns is fixed.
+
+use crate::{propagate::Deduction, Board, BoardError, Cell, Index, BOARD_SIZE};
+
+/// What all the completions of a line have in common.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LineFacts {
+    /// Cells that are walls in every completion.
+    pub walls: u8,
+    /// Cells that are floor in every completion.
+    pub floor: u8,
+    /// Number of completions.
+    pub patterns: u32,
+}
+
+/// Finds what every wall pattern with `count` walls consistent with `line` agrees on.
+/// A hidden count allows any number of walls.
+///
+/// Returns `None` if there is no such pattern, including when `count` is more than the
+/// length of the line.
+pub fn solve_line(line: &[Cell; BOARD_SIZE], count: Option<u8>) -> Option<LineFacts> {
+    let mut known_walls = 0u8;
+    let mut known_floor = 0u8;
+    for (i, cell) in line.iter().enumerate() {
+        match cell {
+            Cell::Unknown => {}
+            Cell::Wall => known_walls |= 1 << i,
+            Cell::Empty | Cell::Monster | Cell::Chest => known_floor |= 1 << i,
+        }
+    }
+
+    let mut facts = LineFacts {
+        walls: u8::MAX,
+        floor: u8::MAX,
+        patterns: 0,
+    };
+    let patterns = match count {
+        Some(count) => &PATTERNS.get(count as usize)?[..],
+        None => &ALL_PATTERNS[..],
+    };
+    for &pattern in patterns {
+        if pattern & known_walls != known_walls || pattern & known_floor != 0 {
+            continue;
+        }
+        facts.walls &= pattern;
+        facts.floor &= !pattern;
+        facts.patterns += 1;
+    }
+
+    (facts.patterns > 0).then_some(facts)
+}
+
+static ALL_PATTERNS: std::sync::LazyLock<Vec<u8>> =
+    std::sync::LazyLock::new(|| (0..=u8::MAX).collect());
+
+// All wall patterns of a line, grouped by the number of walls
+static PATTERNS: std::sync::LazyLock<Vec<Vec<u8>>> = std::sync::LazyLock::new(|| {
+    let mut by_count = vec![Vec::new(); BOARD_SIZE + 1];
+    for pattern in 0..=u8::MAX {
+        by_count[pattern.count_ones() as usize].push(pattern);
+    }
+    by_count
+});
+
+impl Board {
+    /// Checks every row and column against its count with [`solve_line`], and that no
+    /// cell has to be a wall for its row but floor for its column, or the other way round.
+    /// Such a cell is blamed on the line that needs the wall, as its count has no room
+    /// left for it.
+    pub fn lines_acceptable(&self) -> Result<(), BoardError> {
+        self.line_facts().map(|_| ())
+    }
+
+    /// `Unknown` cells fixed by line solving the rows and columns.
+    pub fn line_deductions(&self) -> Result<Vec<Deduction>, BoardError> {
+        let (rows, cols) = self.line_facts()?;
+        let mut forced = Vec::new();
+        for (r, row) in rows.iter().enumerate() {
+            for (c, col) in cols.iter().enumerate() {
+                if !matches!(self.cells[r][c], Cell::Unknown) {
+                    continue;
+                }
+                let wall = row.walls >> c & 1 == 1 || col.walls >> r & 1 == 1;
+                let floor = row.floor >> c & 1 == 1 || col.floor >> r & 1 == 1;
+                // `line_facts` rules out cells that are both
+                if wall {
+                    forced.push(((r as Index, c as Index), Cell::Wall));
+                } else if floor {
+                    forced.push(((r as Index, c as Index), Cell::Empty));
+                }
+            }
+        }
+        Ok(forced)
+    }
+
+    fn line_facts(&self) -> Result<([LineFacts; BOARD_SIZE], [LineFacts; BOARD_SIZE]), BoardError> {
+        let none = LineFacts {
+            walls: 0,
+            floor: 0,
+            patterns: 0,
+        };
+        let mut rows = [none; BOARD_SIZE];
+        let mut cols = [none; BOARD_SIZE];
+        for (i, facts) in rows.iter_mut().enumerate() {
+            *facts = solve_line(&self.cells[i], self.row_counts[i])
+                .ok_or(BoardError::WrongRowCount(i as Index))?;
+        }
+        for (j, facts) in cols.iter_mut().enumerate() {
+            let column = std::array::from_fn(|i| self.cells[i][j]);
+            *facts = solve_line(&column, self.column_counts[j])
+                .ok_or(BoardError::WrongColumnCount(j as Index))?;
+        }
+        for (r, row) in rows.iter().enumerate() {
+            for (c, col) in cols.iter().enumerate() {
+                let row_wall = row.walls >> c & 1 == 1;
+                let row_floor = row.floor >> c & 1 == 1;
+                let col_wall = col.walls >> r & 1 == 1;
+                let col_floor = col.floor >> r & 1 == 1;
+                if row_wall && col_floor {
+                    return Err(BoardError::WrongRowCount(r as Index));
+                }
+                if row_floor && col_wall {
+                    return Err(BoardError::WrongColumnCount(c as Index));
+                }
+            }
+        }
+        Ok((rows, cols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A line from its text: `x` for a wall, `.` for floor and a space for unknown
+    fn line(text: &str) -> [Cell; BOARD_SIZE] {
+        let cells = text.bytes().map(|b| match b {
+            b'x' => Cell::Wall,
+            b'.' => Cell::Empty,
+            _ => Cell::Unknown,
+        });
+        let mut line = [Cell::Unknown; BOARD_SIZE];
+        for (cell, value) in line.iter_mut().zip(cells) {
+            *cell = value;
+        }
+        line
+    }
+
+    fn facts(walls: u8, floor: u8, patterns: u32) -> Option<LineFacts> {
+        Some(LineFacts {
+            walls,
+            floor,
+            patterns,
+        })
+    }
+
+    // An unknown board with hidden counts, apart from the given ones
+    fn unknown_board(row_counts: &[(usize, u8)], column_counts: &[(usize, u8)]) -> Board {
+        let mut board = Board::from_string(
+            " ????????\n?        \n?        \n?        \n?        \n\
+             ?        \n?        \n?        \n?        ",
+        )
+        .unwrap();
+        for &(i, n) in row_counts {
+            board.row_counts[i] = Some(n);
+        }
+        for &(j, n) in column_counts {
+            board.column_counts[j] = Some(n);
+        }
+        board
+    }
+
+    #[test]
+    fn full_and_empty_lines_are_fixed() {
+        assert_eq!(solve_line(&line(""), Some(0)), facts(0, 0xff, 1));
+        assert_eq!(solve_line(&line(""), Some(8)), facts(0xff, 0, 1));
+        // The unknown cell must be the third wall
+        assert_eq!(
+            solve_line(&line("x.x. ..."), Some(3)),
+            facts(0b0001_0101, 0b1110_1010, 1)
+        );
+    }
+
+    #[test]
+    fn cells_that_differ_between_completions_stay_open() {
+        // Two walls left for three cells: the known cells are all that is certain
+        assert_eq!(
+            solve_line(&line("x.   ..."), Some(3)),
+            facts(0b0000_0001, 0b1110_0010, 3)
+        );
+        assert_eq!(
+            solve_line(&line("x.      "), None),
+            facts(0b0000_0001, 0b0000_0010, 64)
+        );
+    }
+
+    #[test]
+    fn lines_without_a_completion_are_rejected() {
+        assert_eq!(solve_line(&line("xxx"), Some(2)), None);
+        assert_eq!(solve_line(&line("....."), Some(4)), None);
+        assert_eq!(solve_line(&line(""), Some(9)), None);
+    }
+
+    #[test]
+    fn deductions_come_from_rows_and_columns() {
+        let board = unknown_board(&[(0, 8)], &[(3, 8)]);
+        let deductions = board.line_deductions().unwrap();
+        assert_eq!(deductions.len(), 8 + 7);
+        assert!(deductions.contains(&((0, 7), Cell::Wall)));
+        assert!(deductions.contains(&((5, 3), Cell::Wall)));
+
+        let board = unknown_board(&[(5, 0)], &[]);
+        let deductions = board.line_deductions().unwrap();
+        assert_eq!(deductions.len(), 8);
+        assert!(deductions.contains(&((5, 3), Cell::Empty)));
+    }
+
+    #[test]
+    fn conflicts_are_blamed_on_the_line_that_needs_a_wall() {
+        // Row 0 needs a wall in the last cell, where column 7 has no room for one
+        let board = unknown_board(&[(0, 8)], &[(7, 0)]);
+        assert_eq!(board.lines_acceptable(), Err(BoardError::WrongRowCount(0)));
+        let board = unknown_board(&[(0, 0)], &[(7, 8)]);
+        assert_eq!(
+            board.lines_acceptable(),
+            Err(BoardError::WrongColumnCount(7))
+        );
+    }
+
+    #[test]
+    fn wrong_counts_are_blamed_on_their_line() {
+        let mut board = unknown_board(&[(2, 1)], &[(5, 1)]);
+        board.cells[2][0] = Cell::Wall;
+        board.cells[2][1] = Cell::Wall;
+        assert_eq!(board.lines_acceptable(), Err(BoardError::WrongRowCount(2)));
+        board.cells[2][1] = Cell::Unknown;
+        board.cells[0][5] = Cell::Wall;
+        board.cells[1][5] = Cell::Wall;
+        assert_eq!(
+            board.lines_acceptable(),
+            Err(BoardError::WrongColumnCount(5))
+        );
+    }
+}
@@ -1,544 +1,328 @@
-use std::fmt;
+use std::{env, fs, io::Read, process, time::Instant};
+
+use zach_dnd_solver::{
+    bench::{bench, strategies, BenchOptions},
+    certificate::Certificate,
+    format::{parse_puzzles, Puzzle},
+    minimize::MinimizeOptions,
+    search::{SolveOptions, SolveStats, ValueOrder, VariableOrder},
+    server::{Server, ServerOptions},
+    solver::SolverKind,
+    symmetry::find_duplicates,
+    Board,
+};
+
+const USAGE: &str = "\
+usage: zach-dnd-solver <COMMAND> <ARGS>
+
+commands:
+    solve [--solver NAME] <PUZZLE>
+                         solve the puzzle and print the solution
+    count [--solver NAME] [--limit N] <PUZZLE>
+                         count the solutions, up to N (default 2)
+    check <PUZZLE>       check whether the board is a valid solution
+    encode <PUZZLE>      print the compact code of the puzzle
+    certify <PUZZLE>     print a certificate that the solution is unique
+    verify <FILE>        check a certificate printed by `certify`
+    minimize [--counts] <PUZZLE>
+                         remove the givens (and with `--counts`, the row and
+                         column counts) not needed for a unique solution
+    compare <FILE>...    solve the puzzles with every branching heuristic
+    dedup <FILE>...      list puzzles that are rotations or reflections of each other
+    bench [--runs N] [--json OUT] [--solver NAME]... <FILE>...
+                         time every solver strategy (or those of the given
+                         solvers) on the puzzles, and write the results as JSON
+                         to OUT; plain backtracking always runs first, as the
+                         baseline for the speedups
+    serve [ADDR]         answer JSON requests over HTTP (default 127.0.0.1:7878)
+
+Solvers are `backtracking`, `propagation` (the default), `sat` and `parallel`.
+PUZZLE is a file with a single puzzle (`-` for standard input),
+or `--code CODE` with a code printed by `encode`. A FILE may hold
+several puzzles separated by blank lines, each optionally preceded by
+`key: value` lines (title, author, source, difficulty, notes).
+
+Without arguments, solves puzzle 5-8 as an example.";
 
-// TODO can impl PartialEq, Eq
-#[derive(Copy, Clone, Debug)]
-enum Cell {
-    Unknown,
-    Empty,
-    Wall,
-    Monster,
-    Chest,
-}
-
-#[derive(Debug)]
-enum BoardError {
-    // At least one cell is `Unsolved`
-    Unsolved, // TODO do we need coordinates?
-    WrongRowCount(Index),
-    WrongColumnCount(Index),
-    MonsterNotInDeadEnd(Index, Index),
-    DeadEndWithNoMontster(Index, Index),
-    NoTreasureRoomForChest(Index, Index),
-    CorridorsTooWide(Index, Index),
-    UnconnectedCorridors,
-}
-
-#[derive(Debug)]
-struct ParseError; // TODO distinguish errors (but nobody actually cares)
-
-type Index = i8;
-const BOARD_SIZE: usize = 8;
-
-#[derive(Debug)]
-struct Board {
-    cells: [[Cell; BOARD_SIZE]; BOARD_SIZE],
-    column_counts: [u8; BOARD_SIZE],
-    row_counts: [u8; BOARD_SIZE],
-}
-
-#[derive(Debug)]
-struct Unsolvable;
-
-impl fmt::Display for Board {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(
-            f,
-            " {}",
-            self.column_counts
-                .into_iter()
-                .map(|n| n.to_string())
-                .collect::<String>()
-        )?;
-        for i in 0..BOARD_SIZE {
-            writeln!(
-                f,
-                "{}{}",
-                self.row_counts[i],
-                self.cells[i]
-                    .into_iter()
-                    .map(|cell| match cell {
-                        Cell::Unknown => ' ',
-                        Cell::Empty => '.',
-                        Cell::Wall => '#',
-                        Cell::Monster => 'M',
-                        Cell::Chest => 'C',
-                    })
-                    .collect::<String>()
-            )?;
-        }
-
-        Ok(())
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        demo();
+        return;
+    }
+    if let Err(msg) = run(&args) {
+        eprintln!("error: {msg}");
+        process::exit(1);
     }
 }
 
-impl Board {
-    fn from_string(spec: &str) -> Result<Board, ParseError> {
-        let mut lines = spec.lines().map(|s| s.as_bytes());
-        let first_line = lines.next().ok_or(ParseError {})?;
-        if first_line.len() != BOARD_SIZE + 1 {
-            return Err(ParseError {});
-        }
-        let mut column_counts = [0u8; BOARD_SIZE];
-        for i in 0..BOARD_SIZE {
-            if !first_line[i + 1].is_ascii_digit() {
-                return Err(ParseError {});
-            }
-            column_counts[i] = first_line[i + 1] - b'0';
+fn run(args: &[String]) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    match command.as_str() {
+        "solve" => {
+            let (kind, rest) = solver_flag(rest)?;
+            let board = load_puzzle(rest)?;
+            println!("{board}");
+            let solution = kind
+                .solver(SolveOptions::default())
+                .solve(&board)
+                .map_err(|_| "unsolvable")?;
+            println!("{solution}");
         }
-        let mut row_counts = [0u8; BOARD_SIZE];
-        let mut cells = [[Cell::Empty; BOARD_SIZE]; BOARD_SIZE];
-        for (i, line) in lines.enumerate() {
-            if line.len() != BOARD_SIZE + 1 || !line[0].is_ascii_digit() {
-                return Err(ParseError {});
-            }
-            row_counts[i] = line[0] - b'0';
-            for j in 0..BOARD_SIZE {
-                cells[i][j] = match line[j + 1] {
-                    b' ' => Cell::Unknown,
-                    b'.' => Cell::Empty,
-                    b'#' => Cell::Wall,
-                    b'M' => Cell::Monster,
-                    b'C' => Cell::Chest,
-                    _ => return Err(ParseError {}),
+        "count" => {
+            let (mut kind, mut limit) = (SolverKind::default(), 2);
+            let mut rest = rest;
+            loop {
+                match rest {
+                    [flag, name, tail @ ..] if flag == "--solver" => {
+                        kind = parse_solver(name)?;
+                        rest = tail;
+                    }
+                    [flag, n, tail @ ..] if flag == "--limit" => {
+                        limit = n.parse().map_err(|_| format!("invalid limit `{n}`"))?;
+                        rest = tail;
+                    }
+                    _ => break,
                 }
             }
+            let board = load_puzzle(rest)?;
+            let mut solver = kind.solver(SolveOptions::default());
+            let count = solver.count(&board, limit);
+            let stats = solver.stats();
+            println!("{count} solutions ({} nodes)", stats.nodes);
         }
-
-        Ok(Board {
-            cells,
-            column_counts,
-            row_counts,
-        })
-    }
-
-    fn rows_acceptable(&self) -> Result<(), Index> {
-        let unknown_counts = self
-            .cells
-            .iter()
-            .map(|row| row.iter().filter(|c| matches!(c, Cell::Unknown)).count());
-        let wall_counts = self
-            .cells
-            .iter()
-            .map(|row| row.iter().filter(|c| matches!(c, Cell::Wall)).count());
-
-        let ranges = wall_counts
-            .zip(unknown_counts)
-            .map(|(walls, unkns)| (walls as u8..=(walls + unkns) as u8));
-
-        ranges
-            .zip(self.row_counts)
-            .enumerate()
-            .find_map(|(i, (range, cnt))| (!range.contains(&cnt)).then_some(i))
-            .map_or(Ok(()), |i| Err(i as Index))
-    }
-
-    fn cols_acceptable(&self) -> Result<(), Index> {
-        let columns = (0..BOARD_SIZE).map(|i| self.cells.iter().map(move |row| row[i]));
-        let wall_counts = columns
-            .clone()
-            .map(|col| col.filter(|c| matches!(c, Cell::Wall)).count() as u8);
-        let unkn_counts =
-            columns.map(|col| col.filter(|c| matches!(c, Cell::Unknown)).count() as u8);
-        let ranges = wall_counts
-            .zip(unkn_counts)
-            .map(|(walls, unkns)| (walls..=walls + unkns));
-
-        ranges
-            .zip(self.column_counts)
-            .enumerate()
-            .find_map(|(i, (range, cnt))| (!range.contains(&cnt)).then_some(i))
-            .map_or(Ok(()), |i| Err(i as Index))
-    }
-
-    fn check_solved(&self) -> Result<(), BoardError> {
-        // * No `Unknown`
-        // * All column & row counts are satisfied
-        // * Monsters are in dead ends; all dead ends have monsters
-        // * All treasure rooms have 3x3 empty space and one entrance
-        // * No 2x2 empty spaces
-        // * All corridors connected
-        use BoardError as E;
-
-        if self
-            .cells
-            .iter()
-            .flatten()
-            .any(|c| matches!(c, Cell::Unknown))
-        {
-            return Err(E::Unsolved);
+        "check" => {
+            let board = load_puzzle(rest)?;
+            println!("{:?}", board.check_solved());
         }
-
-        let cur_row_counts = self
-            .cells
-            .iter()
-            .map(|row| row.iter().filter(|c| matches!(c, Cell::Wall)).count() as u8);
-        let bad_row = cur_row_counts
-            .zip(self.row_counts)
-            .enumerate()
-            .find_map(|(i, (a, b))| (a != b).then_some(i));
-        if let Some(r) = bad_row {
-            return Err(E::WrongRowCount(r as Index));
+        "encode" => {
+            let board = load_puzzle(rest)?;
+            println!("{}", board.to_code());
         }
-
-        let columns = (0..BOARD_SIZE).map(|i| self.cells.iter().map(move |row| row[i]));
-        let cur_col_counts =
-            columns.map(|col| col.filter(|c| matches!(c, Cell::Wall)).count() as u8);
-        let bad_col = cur_col_counts
-            .zip(self.column_counts)
-            .enumerate()
-            .find_map(|(i, (a, b))| (a != b).then_some(i));
-        if let Some(c) = bad_col {
-            return Err(E::WrongColumnCount(c as Index));
+        "certify" => {
+            let board = load_puzzle(rest)?;
+            let certificate = board
+                .certify()
+                .map_err(|e| format!("cannot certify: {e:?}"))?;
+            print!("{certificate}");
         }
-
-        let mut treasure_rooms = Vec::<(Index, Index)>::new();
-
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                let is_monster = matches!(self.cells[i][j], Cell::Monster);
-                let is_dead_end = self.is_dead_end(i as Index, j as Index);
-                if is_monster != is_dead_end {
-                    // "if and only if" relation
-                    return if is_monster {
-                        Err(E::MonsterNotInDeadEnd(i as Index, j as Index))
-                    } else {
-                        Err(E::DeadEndWithNoMontster(i as Index, j as Index))
-                    };
-                }
-
-                if let Cell::Chest = self.cells[i][j] {
-                    let (r, c) = (i as Index, j as Index);
-                    let treasure_room_candidates = [
-                        (r - 2, c - 2),
-                        (r - 2, c - 1),
-                        (r - 2, c),
-                        (r - 1, c - 2),
-                        (r - 1, c - 1),
-                        (r - 1, c),
-                        (r, c - 2),
-                        (r, c - 1),
-                        (r, c),
-                    ];
-                    let maybe_room = treasure_room_candidates
-                        .into_iter()
-                        .find(|&(r, c)| self.is_treasure_room(r, c));
-                    match maybe_room {
-                        Some(room) => {
-                            treasure_rooms.push(room);
-                        }
-                        None => {
-                            return Err(E::NoTreasureRoomForChest(i as Index, j as Index));
-                        }
-                    }
-                }
+        "verify" => {
+            let [path] = rest else {
+                return Err(USAGE.to_string());
+            };
+            let spec = read_file(path)?;
+            let certificate = Certificate::from_string(&spec)
+                .map_err(|_| format!("`{path}` is not a valid certificate"))?;
+            match certificate.check() {
+                Ok(()) => println!("ok"),
+                Err(e) => return Err(format!("invalid certificate: {e:?}")),
             }
         }
-
-        let coords_to_check = {
-            let mut check = [[true; BOARD_SIZE]; BOARD_SIZE];
-            // . # # # # .
-            // # # # # # #
-            // # # # # # #
-            // # # # # # #
-            // # # # # # #
-            // . # # # # .
-            let affected_coords = (-1..=2)
-                .map(|c| (-2, c)) // rect (-2, -1) ..= (-2, +2)
-                .chain(
-                    // rect (-1, -2) ..= (+2, +3)
-                    (-1..=2).flat_map(|r| (-2..=3).map(move |c| (r, c))),
-                )
-                .chain(
-                    // rect (+3, -1) ..= (+3, +2)
-                    (-1..=2).map(|c| (3, c)),
-                );
-
-            for (r, c) in treasure_rooms {
-                affected_coords
-                    .clone()
-                    .map(|(dr, dc)| (r + dr, c + dc))
-                    .filter_map(|(r, c)| {
-                        (self.is_in_bounds(r, c)).then_some((r as usize, c as usize))
-                    })
-                    .for_each(|(r, c)| {
-                        check[r][c] = false;
-                    });
+        "minimize" => {
+            let (counts, rest) = match rest {
+                [flag, rest @ ..] if flag == "--counts" => (true, rest),
+                _ => (false, rest),
+            };
+            let board = load_puzzle(rest)?;
+            let minimized = board
+                .minimize(&MinimizeOptions { counts })
+                .map_err(|e| format!("cannot minimize: {e:?}"))?;
+            println!("{}", minimized.puzzle);
+            println!("removed {} givens:", minimized.removed.len());
+            for (r, c) in minimized.removed {
+                println!("    ({r}, {c})");
             }
-
-            check
-        };
-
-        for i in 0..BOARD_SIZE - 1 {
-            for j in 0..BOARD_SIZE - 1 {
-                if !coords_to_check[i][j] {
-                    continue;
-                }
-                let is_empty_2x2 = [(i, j), (i, j + 1), (i + 1, j), (i + 1, j + 1)]
-                    .into_iter()
-                    .all(|(i, j)| matches!(self.cells[i][j], Cell::Empty));
-                if is_empty_2x2 {
-                    return Err(E::CorridorsTooWide(i as Index, j as Index));
+            if counts {
+                println!("hid {} counts:", minimized.hidden_counts.len());
+                for line in minimized.hidden_counts {
+                    println!("    {line:?}");
                 }
             }
         }
-
-        let first_empty_cell = (0..BOARD_SIZE)
-            .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
-            .find(|&(r, c)| matches!(self.cells[r][c], Cell::Empty))
-            .map(|(r, c)| (r as Index, c as Index));
-        let mut to_check: Vec<(Index, Index)> = first_empty_cell.into_iter().collect();
-        let mut seen = [[false; BOARD_SIZE]; BOARD_SIZE];
-        let mut connected_cells: u32 = 0;
-
-        while let Some((r, c)) = to_check.pop() {
-            let seen_this = &mut seen[r as usize][c as usize];
-            if *seen_this {
-                continue;
+        "compare" => {
+            let puzzles = load_files(rest)?
+                .into_iter()
+                .map(|(_, puzzle)| puzzle.board)
+                .collect::<Vec<_>>();
+            if puzzles.is_empty() {
+                return Err(USAGE.to_string());
             }
-            *seen_this = true;
-            connected_cells += 1;
-            let neighbors = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)];
-            to_check.extend(
-                // TODO this kinda ugly... `seen` is unelegant & maybe a footgun
-                neighbors
-                    .into_iter()
-                    .filter(|&(r, c)| !matches!(self.at(r, c), Cell::Wall)),
-            )
+            compare(&puzzles);
         }
-
-        if first_empty_cell.is_none() {
-            return Ok(()); // unlikely, but who knows?
+        "bench" => {
+            let mut options = BenchOptions::default();
+            let mut json = None;
+            let mut solvers = Vec::new();
+            let mut rest = rest;
+            loop {
+                match rest {
+                    [flag, name, tail @ ..] if flag == "--solver" => {
+                        solvers.push(parse_solver(name)?);
+                        rest = tail;
+                    }
+                    [flag, runs, tail @ ..] if flag == "--runs" => {
+                        options.runs = runs
+                            .parse()
+                            .map_err(|_| format!("invalid number of runs `{runs}`"))?;
+                        rest = tail;
+                    }
+                    [flag, path, tail @ ..] if flag == "--json" => {
+                        json = Some(path);
+                        rest = tail;
+                    }
+                    _ => break,
+                }
+            }
+            let puzzles = load_files(rest)?
+                .into_iter()
+                .map(|(name, puzzle)| (name, puzzle.board))
+                .collect::<Vec<_>>();
+            if puzzles.is_empty() {
+                return Err(USAGE.to_string());
+            }
+            // The baseline stays first, to compare against
+            let strategies = strategies()
+                .into_iter()
+                .enumerate()
+                .filter(|(i, s)| *i == 0 || solvers.is_empty() || solvers.contains(&s.solver))
+                .map(|(_, s)| s)
+                .collect::<Vec<_>>();
+            let report = bench(&puzzles, &strategies, &options);
+            print!("{report}");
+            if let Some(path) = json {
+                fs::write(path, format!("{}\n", report.to_json()))
+                    .map_err(|e| format!("cannot write `{path}`: {e}"))?;
+            }
         }
-
-        let total_empty = self
-            .cells
-            .iter()
-            .flatten()
-            .filter(|&c| !matches!(c, Cell::Wall))
-            .count() as u32;
-
-        // All empty cells are connected
-        if connected_cells != total_empty {
-            return Err(E::UnconnectedCorridors);
+        "dedup" => {
+            let (names, puzzles): (Vec<_>, Vec<_>) = load_files(rest)?
+                .into_iter()
+                .map(|(name, puzzle)| (name, puzzle.board))
+                .unzip();
+            if puzzles.is_empty() {
+                return Err(USAGE.to_string());
+            }
+            for group in find_duplicates(&puzzles) {
+                let names = group.iter().map(|&i| names[i].as_str()).collect::<Vec<_>>();
+                println!("{}", names.join(" "));
+            }
         }
-
-        Ok(())
-    }
-
-    // Accepts out-of-bounds coordinates, and assumes there are walls everywhere outside the board.
-    fn at(&self, r: Index, c: Index) -> Cell {
-        if self.is_in_bounds(r, c) {
-            self.cells[r as usize][c as usize]
-        } else {
-            Cell::Wall
+        "serve" => {
+            let addr = match rest {
+                [] => "127.0.0.1:7878",
+                [addr] => addr.as_str(),
+                _ => return Err(USAGE.to_string()),
+            };
+            let server = Server::bind(addr, ServerOptions::default())
+                .map_err(|e| format!("cannot listen on {addr}: {e}"))?;
+            if let Ok(addr) = server.local_addr() {
+                eprintln!("listening on http://{addr}");
+            }
+            server.run().map_err(|e| e.to_string())?;
         }
+        "-h" | "--help" | "help" => println!("{USAGE}"),
+        _ => return Err(format!("unknown command `{command}`\n\n{USAGE}")),
     }
+    Ok(())
+}
 
-    fn is_in_bounds(&self, r: Index, c: Index) -> bool {
-        (0..BOARD_SIZE as Index).contains(&r) && (0..BOARD_SIZE as Index).contains(&c)
-    }
-
-    fn is_dead_end(&self, r: Index, c: Index) -> bool {
-        if matches!(self.at(r, c), Cell::Unknown | Cell::Wall) {
-            return false;
-        }
-        let surrounding_wall_count = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)]
-            .into_iter()
-            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
-            .count();
-
-        surrounding_wall_count == 3
+/// Splits an optional `--solver NAME` off the front of the arguments.
+fn solver_flag(args: &[String]) -> Result<(SolverKind, &[String]), String> {
+    match args {
+        [flag, name, rest @ ..] if flag == "--solver" => Ok((parse_solver(name)?, rest)),
+        _ => Ok((SolverKind::default(), args)),
     }
+}
 
-    fn maybe_dead_end(&self, r: Index, c: Index) -> bool {
-        let surrounding_cells = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)];
-        let walls = surrounding_cells
-            .into_iter()
-            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
-            .count();
-        let air = surrounding_cells
-            .into_iter()
-            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Empty))
-            .count();
-
-        walls <= 3 && air <= 1
-    }
+fn parse_solver(name: &str) -> Result<SolverKind, String> {
+    SolverKind::from_name(name).ok_or_else(|| format!("unknown solver `{name}`"))
+}
 
-    fn maybe_treasure_room(&self, r: Index, c: Index) -> bool {
-        let inside_coords = [
-            (r, c),
-            (r, c + 1),
-            (r, c + 2),
-            (r + 1, c),
-            (r + 1, c + 1),
-            (r + 1, c + 2),
-            (r + 2, c),
-            (r + 2, c + 1),
-            (r + 2, c + 2),
-        ];
-        let mut chest_seen = false;
-        for (r, c) in inside_coords {
-            match self.at(r, c) {
-                Cell::Chest => {
-                    if chest_seen {
-                        return false;
-                    };
-                    chest_seen = true;
-                }
-                Cell::Empty | Cell::Unknown => {}
-                _ => {
-                    return false;
-                }
-            }
+/// Reads a puzzle from the `PUZZLE` part of the command line.
+fn load_puzzle(args: &[String]) -> Result<Board, String> {
+    match args {
+        [flag, code] if flag == "--code" || flag == "-c" => {
+            Board::from_code(code).map_err(|e| format!("invalid puzzle code: {e:?}"))
         }
-        let outside_coords = [
-            // top
-            (r - 1, c),
-            (r - 1, c + 1),
-            (r - 1, c + 2),
-            //left-right
-            (r, c - 1),
-            (r, c + 3),
-            (r + 1, c - 1),
-            (r + 1, c + 3),
-            (r + 2, c - 1),
-            (r + 2, c + 3),
-            // bottom
-            (r + 3, c),
-            (r + 3, c + 1),
-            (r + 3, c + 2),
-        ];
-        let wall_count = outside_coords
-            .into_iter()
-            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
-            .count();
-        let unknown_count = outside_coords
-            .into_iter()
-            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Unknown))
-            .count();
-
-        (wall_count..=wall_count + unknown_count).contains(&(outside_coords.len() - 1))
+        [path] => match &read_puzzles(path)?[..] {
+            [puzzle] => Ok(puzzle.board.clone()),
+            puzzles => Err(format!(
+                "`{path}` holds {} puzzles, expected one",
+                puzzles.len()
+            )),
+        },
+        _ => Err(USAGE.to_string()),
     }
+}
 
-    fn is_treasure_room(&self, r: Index, c: Index) -> bool {
-        let inside_coords = [
-            (r, c),
-            (r, c + 1),
-            (r, c + 2),
-            (r + 1, c),
-            (r + 1, c + 1),
-            (r + 1, c + 2),
-            (r + 2, c),
-            (r + 2, c + 1),
-            (r + 2, c + 2),
-        ];
-        let mut chest_seen = false;
-        for (r, c) in inside_coords {
-            match self.at(r, c) {
-                Cell::Chest => {
-                    if chest_seen {
-                        return false;
-                    };
-                    chest_seen = true;
-                }
-                Cell::Empty => {}
-                _ => {
-                    return false;
-                }
-            }
-        }
-        let outside_coords = [
-            // top
-            (r - 1, c),
-            (r - 1, c + 1),
-            (r - 1, c + 2),
-            //left-right
-            (r, c - 1),
-            (r, c + 3),
-            (r + 1, c - 1),
-            (r + 1, c + 3),
-            (r + 2, c - 1),
-            (r + 2, c + 3),
-            // bottom
-            (r + 3, c),
-            (r + 3, c + 1),
-            (r + 3, c + 2),
-        ];
-        let wall_count = outside_coords
-            .into_iter()
-            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
-            .count();
-
-        wall_count == outside_coords.len() - 1
+/// Reads a file, or standard input for `-`.
+fn read_file(path: &str) -> Result<String, String> {
+    if path == "-" {
+        let mut spec = String::new();
+        std::io::stdin()
+            .read_to_string(&mut spec)
+            .map_err(|e| format!("cannot read standard input: {e}"))?;
+        Ok(spec)
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("cannot read `{path}`: {e}"))
     }
+}
 
-    fn solve(&mut self) -> Result<(), Unsolvable> {
-        let first_unknown = (0..BOARD_SIZE)
-            .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
-            .find(|&(r, c)| matches!(self.cells[r][c], Cell::Unknown));
-        if let Some((r, c)) = first_unknown {
-            self.cells[r][c] = Cell::Wall;
-            if self.maybe_solvable().is_ok() && self.solve().is_ok() {
-                return Ok(());
-            }
-            self.cells[r][c] = Cell::Empty;
-            if self.maybe_solvable().is_ok() && self.solve().is_ok() {
-                return Ok(());
-            }
-            self.cells[r][c] = Cell::Unknown;
-
-            Err(Unsolvable)
-        } else {
-            self.check_solved().or(Err(Unsolvable))
+/// Reads every puzzle in the files, named by their path, followed by their position
+/// in the file if it holds more than one.
+fn load_files(paths: &[String]) -> Result<Vec<(String, Puzzle)>, String> {
+    let mut named = Vec::new();
+    for path in paths {
+        let puzzles = read_puzzles(path)?;
+        let several = puzzles.len() > 1;
+        for (i, puzzle) in puzzles.into_iter().enumerate() {
+            let name = if several {
+                format!("{path}:{}", i + 1)
+            } else {
+                path.clone()
+            };
+            named.push((name, puzzle));
         }
     }
+    Ok(named)
+}
 
-    fn maybe_solvable(&self) -> Result<(), BoardError> {
-        self.rows_acceptable()
-            .map_err(|i| BoardError::WrongRowCount(i))?;
-        self.cols_acceptable()
-            .map_err(|i| BoardError::WrongColumnCount(i))?;
-
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                let is_monster = matches!(self.cells[i][j], Cell::Monster);
-                let (r, c) = (i as Index, j as Index);
-                let maybe_dead_end = self.maybe_dead_end(r, c);
-                let is_dead_end = self.is_dead_end(r, c);
-                if is_monster && !maybe_dead_end {
-                    return Err(BoardError::MonsterNotInDeadEnd(r, c));
-                }
-                if !is_monster && is_dead_end {
-                    return Err(BoardError::DeadEndWithNoMontster(r, c));
-                }
+fn read_puzzles(path: &str) -> Result<Vec<Puzzle>, String> {
+    let spec = read_file(path)?;
+    parse_puzzles(&spec).map_err(|e| format!("`{path}` is not a valid puzzle file: {e:?}"))
+}
 
-                if let Cell::Chest = self.cells[i][j] {
-                    let treasure_room_candidates = [
-                        (r - 2, c - 2),
-                        (r - 2, c - 1),
-                        (r - 2, c),
-                        (r - 1, c - 2),
-                        (r - 1, c - 1),
-                        (r - 1, c),
-                        (r, c - 2),
-                        (r, c - 1),
-                        (r, c),
-                    ];
-                    let maybe_room = treasure_room_candidates
-                        .into_iter()
-                        .find(|&(r, c)| self.maybe_treasure_room(r, c));
-                    if maybe_room.is_none() {
-                        return Err(BoardError::NoTreasureRoomForChest(r, c));
-                    }
-                }
+fn compare(puzzles: &[Board]) {
+    println!(
+        "{:<16} {:<15} {:>7} {:>10} {:>10} {:>10}",
+        "variable order", "value order", "solved", "nodes", "backtracks", "time (ms)"
+    );
+    for variable_order in VariableOrder::ALL {
+        for value_order in ValueOrder::ALL {
+            let options = SolveOptions {
+                variable_order,
+                value_order,
+            };
+            let mut stats = SolveStats::default();
+            let mut solved = 0;
+            let start = Instant::now();
+            for puzzle in puzzles {
+                solved += puzzle.clone().solve_with(&options, &mut stats).is_ok() as usize;
             }
+            println!(
+                "{:<16} {:<15} {:>7} {:>10} {:>10} {:>10.2}",
+                format!("{variable_order:?}"),
+                format!("{value_order:?}"),
+                solved,
+                stats.nodes,
+                stats.backtracks,
+                start.elapsed().as_secs_f64() * 1000.0
+            );
         }
-
-        Ok(())
     }
 }
 
-fn main() {
+fn demo() {
     let mut puzzle_5_8 = Board::from_string(
         " 35344253\n\
          4M   M M \n\
@@ -554,138 +338,4 @@ fn main() {
     println!("{:}", puzzle_5_8);
     puzzle_5_8.solve().expect("unsolvable");
     println!("{:}", puzzle_5_8);
-
-    let good1 = Board::from_string(
-        " 88888888\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########",
-    )
-    .unwrap();
-    let good2 = Board::from_string(
-        " 88878888\n\
-         8########\n\
-         8########\n\
-         7###.####\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########",
-    )
-    .unwrap();
-    let good3 = Board::from_string(
-        " 87775658\n\
-         8########\n\
-         5####...#\n\
-         3#M...#.#\n\
-         5####...#\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########",
-    )
-    .unwrap();
-    let good4 = Board::from_string(
-        " 35255888\n\
-         8########\n\
-         5##...###\n\
-         5##..C###\n\
-         3.....###\n\
-         7.#######\n\
-         5...#####\n\
-         6.#.#####\n\
-         5...#####",
-    )
-    .unwrap();
-
-    println!("good1.check_solved() = {:?}", good1.check_solved());
-    println!("good2.check_solved() = {:?}", good2.check_solved());
-    println!("good3.check_solved() = {:?}", good3.check_solved());
-    println!("good4.check_solved() = {:?}", good4.check_solved());
-
-    let bad1 = Board::from_string(
-        " 88888188\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########",
-    )
-    .unwrap();
-    let bad2 = Board::from_string(
-        " 88877688\n\
-         8########\n\
-         7#####.##\n\
-         5###...##\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########",
-    )
-    .unwrap();
-    let bad3 = Board::from_string(
-        " 88885658\n\
-         8########\n\
-         5####...#\n\
-         6####.#.#\n\
-         5####M..#\n\
-         8########\n\
-         8########\n\
-         8########\n\
-         8########",
-    )
-    .unwrap();
-    let bad4 = Board::from_string(
-        " 56443888\n\
-         8########\n\
-         5##...###\n\
-         5##..C###\n\
-         3.....###\n\
-         6.###.###\n\
-         3.....###\n\
-         8########\n\
-         8########",
-    )
-    .unwrap();
-    let bad5 = Board::from_string(
-        " 84645658\n\
-         8########\n\
-         5#...####\n\
-         6#.#.####\n\
-         6#.#.####\n\
-         5#...####\n\
-         5####...#\n\
-         6####.#.#\n\
-         5####...#",
-    )
-    .unwrap();
-    let bad6 = Board::from_string(
-        " 88882458\n\
-         8########\n\
-         8########\n\
-         6####..##\n\
-         6####..##\n\
-         7####.###\n\
-         5####...#\n\
-         6####.#.#\n\
-         5####...#",
-    )
-    .unwrap();
-
-    println!("bad1.check_solved() = {:?}", bad1.check_solved());
-    println!("bad2.check_solved() = {:?}", bad2.check_solved());
-    println!("bad3.check_solved() = {:?}", bad3.check_solved());
-    println!("bad4.check_solved() = {:?}", bad4.check_solved());
-    println!("bad5.check_solved() = {:?}", bad5.check_solved());
-    println!("bad6.check_solved() = {:?}", bad6.check_solved());
 }
@@ -0,0 +1,215 @@
+//! Removing unneeded givens from a puzzle.
+
+use crate::{
+    search::{NodeBudget, Search, SolveOptions},
+    zobrist::TranspositionTable,
+    Board, Cell, Index, BOARD_SIZE,
+};
+
+const TABLE_SIZE: usize = 1 << 16;
+
+#[derive(Debug)]
+pub enum MinimizeError {
+    NoSolution,
+    MultipleSolutions,
+    BudgetExceeded,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MinimizeOptions {
+    /// Also hide row and column counts, after clearing the cells.
+    pub counts: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Line {
+    Row(Index),
+    Column(Index),
+}
+
+#[derive(Clone, Debug)]
+pub struct Minimized {
+    pub puzzle: Board,
+    /// Cells whose given `Wall` or `Empty` was cleared, in reading order.
+    pub removed: Vec<(Index, Index)>,
+    /// Lines whose count was hidden, rows first.
+    pub hidden_counts: Vec<Line>,
+}
+
+impl Board {
+    /// Clears given `Wall` and `Empty` cells for as long as the puzzle keeps exactly one
+    /// solution. Cells placed on top of the puzzle are dropped first.
+    ///
+    /// Givens are tried in reading order, so the result is minimal in the sense that none
+    /// of the remaining givens can be cleared on its own, though a different order might
+    /// clear more of them. With `options.counts`, the counts are then hidden in the same way.
+    pub fn minimize(&self, options: &MinimizeOptions) -> Result<Minimized, MinimizeError> {
+        self.minimize_within(options, &mut NodeBudget(u64::MAX))
+    }
+
+    /// Like [`Board::minimize`], but gives up with [`MinimizeError::BudgetExceeded`] once
+    /// its searches have used up `budget`.
+    pub fn minimize_within(
+        &self,
+        options: &MinimizeOptions,
+        budget: &mut NodeBudget,
+    ) -> Result<Minimized, MinimizeError> {
+        let mut table = TranspositionTable::new(TABLE_SIZE);
+        let mut count = |board: &Board, table: TranspositionTable| {
+            let mut search = Search::with_table(board.clone(), SolveOptions::default(), table);
+            let solutions = search
+                .count_solutions_within(2, budget)
+                .map_err(|_| MinimizeError::BudgetExceeded)?;
+            Ok((solutions, search.into_table().unwrap()))
+        };
+
+        let mut puzzle = self.puzzle();
+        let solutions;
+        (solutions, table) = count(&puzzle, table)?;
+        match solutions {
+            0 => return Err(MinimizeError::NoSolution),
+            1 => {}
+            _ => return Err(MinimizeError::MultipleSolutions),
+        }
+
+        let mut removed = Vec::new();
+        for r in 0..BOARD_SIZE {
+            for c in 0..BOARD_SIZE {
+                let given = puzzle.cells[r][c];
+                if !matches!(given, Cell::Wall | Cell::Empty) || !puzzle.givens[r][c] {
+                    continue;
+                }
+                puzzle.cells[r][c] = Cell::Unknown;
+                puzzle.givens[r][c] = false;
+                let solutions;
+                (solutions, table) = count(&puzzle, table)?;
+                if solutions == 1 {
+                    removed.push((r as Index, c as Index));
+                } else {
+                    puzzle.cells[r][c] = given;
+                    puzzle.givens[r][c] = true;
+                }
+            }
+        }
+
+        let mut hidden_counts = Vec::new();
+        if options.counts {
+            let rows = (0..BOARD_SIZE as Index).map(Line::Row);
+            let columns = (0..BOARD_SIZE as Index).map(Line::Column);
+            for line in rows.chain(columns) {
+                let Some(given) = line.count_of(&mut puzzle).take() else {
+                    continue;
+                };
+                let solutions;
+                (solutions, table) = count(&puzzle, table)?;
+                if solutions == 1 {
+                    hidden_counts.push(line);
+                } else {
+                    *line.count_of(&mut puzzle) = Some(given);
+                }
+            }
+        }
+
+        Ok(Minimized {
+            puzzle,
+            removed,
+            hidden_counts,
+        })
+    }
+}
+
+impl Line {
+    fn count_of(self, board: &mut Board) -> &mut Option<u8> {
+        match self {
+            Line::Row(i) => &mut board.row_counts[i as usize],
+            Line::Column(i) => &mut board.column_counts[i as usize],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::GenerateOptions;
+
+    // Last Call BBS 5-8, with some of its column counts hidden
+    fn puzzle(column_counts: &str) -> Board {
+        Board::from_string(&format!(
+            " {column_counts}\n4M   M M \n4        \n2M       \n4       M\n\
+             6M       \n2       M\n3        \n4 M   M M"
+        ))
+        .unwrap()
+    }
+
+    // The solution of 5-8, with every cell given.
+    fn solution() -> Board {
+        let mut solution = puzzle("35344253");
+        solution.solve().unwrap();
+        solution.givens = [[true; BOARD_SIZE]; BOARD_SIZE];
+        solution
+    }
+
+    #[test]
+    fn minimized_puzzles_keep_their_solution() {
+        let solution = solution();
+        let minimized = solution.minimize(&MinimizeOptions::default()).unwrap();
+        let puzzle = &minimized.puzzle;
+        assert_eq!(puzzle.count_solutions(2), 1);
+        let mut solved = puzzle.clone();
+        solved.solve().unwrap();
+        assert_eq!(solved.cells, solution.cells);
+
+        assert!(!minimized.removed.is_empty());
+        for &(r, c) in &minimized.removed {
+            assert_eq!(puzzle.at(r, c), Cell::Unknown);
+        }
+        // None of the givens left can go
+        for r in 0..BOARD_SIZE {
+            for c in 0..BOARD_SIZE {
+                if matches!(puzzle.cells[r][c], Cell::Wall | Cell::Empty) {
+                    let mut fewer = puzzle.clone();
+                    fewer.cells[r][c] = Cell::Unknown;
+                    fewer.givens[r][c] = false;
+                    assert_eq!(fewer.count_solutions(2), 2, "({r}, {c})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counts_are_hidden_while_the_solution_stays_unique() {
+        // A generated puzzle: its treasure room keeps the searches short, unlike 5-8's
+        let generated = Board::generate(&GenerateOptions::default());
+        let minimized = generated
+            .puzzle
+            .minimize(&MinimizeOptions { counts: true })
+            .unwrap();
+        let puzzle = &minimized.puzzle;
+        assert!(!minimized.hidden_counts.is_empty());
+        for line in &minimized.hidden_counts {
+            let count = match *line {
+                Line::Row(i) => puzzle.row_counts[i as usize],
+                Line::Column(i) => puzzle.column_counts[i as usize],
+            };
+            assert_eq!(count, None);
+        }
+        assert_eq!(puzzle.count_solutions(2), 1);
+    }
+
+    #[test]
+    fn only_unique_puzzles_are_minimized() {
+        let options = MinimizeOptions::default();
+        assert!(matches!(
+            puzzle("???44253").minimize(&options),
+            Err(MinimizeError::MultipleSolutions)
+        ));
+        assert!(matches!(
+            puzzle("85344253").minimize(&options),
+            Err(MinimizeError::NoSolution)
+        ));
+        assert!(matches!(
+            puzzle("35344253").minimize_within(&options, &mut NodeBudget(1)),
+            Err(MinimizeError::BudgetExceeded)
+        ));
+    }
+}
@@ -0,0 +1,34 @@
+//! Deductions applied by the solver before branching.
+
+use crate::{Board, BoardError, Cell, Index};
+
+/// A cell and the value it is forced to have.
+pub type Deduction = ((Index, Index), Cell);
+
+impl Board {
+    /// Repeatedly fills in the cells that are forced by the current state of the board.
+    ///
+    /// Fails if the board turns out to be unsolvable; the cells filled in so far are left
+    /// in place.
+    pub fn propagate(&mut self) -> Result<(), BoardError> {
+        loop {
+            self.maybe_solvable()?;
+            let forced = self.deductions()?;
+            if forced.is_empty() {
+                return Ok(());
+            }
+            for ((r, c), cell) in forced {
+                self.cells[r as usize][c as usize] = cell;
+            }
+        }
+    }
+
+    /// `Unknown` cells whose value follows from the current state of the board.
+    pub fn deductions(&self) -> Result<Vec<Deduction>, BoardError> {
+        let mut forced = self.line_deductions()?;
+        let floor = self.forced_floor_cells().into_iter();
+        forced.extend(floor.map(|rc| (rc, Cell::Empty)));
+        forced.extend(self.forced_walls().into_iter().map(|rc| (rc, Cell::Wall)));
+        Ok(forced)
+    }
+}
@@ -0,0 +1,45 @@
+//! A small deterministic random number generator, for hashing keys and generating puzzles.
+
+/// The splitmix64 generator: small, fast and good enough for hashing and puzzle generation.
+/// The same seed always gives the same numbers.
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, for small `n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_give_the_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = SplitMix64(seed);
+            [(); 4].map(|()| rng.next_u64())
+        };
+        assert_eq!(numbers(7), numbers(7));
+        assert_ne!(numbers(7), numbers(8));
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = SplitMix64(0);
+        let mut seen = [false; 5];
+        for _ in 0..100 {
+            seen[rng.below(5)] = true;
+        }
+        assert_eq!(seen, [true; 5]);
+    }
+}
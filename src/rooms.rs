@@ -0,0 +1,151 @@
+//! Treasure rooms as structured data, for solved and partially solved boards.
+
+use crate::{
+    room_inside_coords, room_outside_coords, treasure_room_candidates, Board, Cell, Index,
+    BOARD_SIZE,
+};
+
+/// A 3x3 treasure room.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TreasureRoom {
+    /// Top-left corner of the room.
+    pub top: Index,
+    pub left: Index,
+    pub chest: (Index, Index),
+    /// The only non-wall cell next to the room.
+    pub entrance: (Index, Index),
+}
+
+impl TreasureRoom {
+    pub const SIZE: Index = 3;
+
+    pub fn contains(&self, r: Index, c: Index) -> bool {
+        (self.top..self.top + Self::SIZE).contains(&r)
+            && (self.left..self.left + Self::SIZE).contains(&c)
+    }
+
+    pub fn cells(&self) -> [(Index, Index); 9] {
+        room_inside_coords(self.top, self.left)
+    }
+}
+
+/// The rooms a chest can still end up in on a partially solved board.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoomCandidates {
+    pub chest: (Index, Index),
+    /// Top-left corners of the candidate rooms.
+    pub corners: Vec<(Index, Index)>,
+}
+
+impl Board {
+    /// Treasure rooms found around the chests, as `check_solved` sees them.
+    ///
+    /// Chests with no complete room around them are skipped.
+    pub fn treasure_rooms(&self) -> Vec<TreasureRoom> {
+        self.chests()
+            .filter_map(|(r, c)| {
+                let (top, left) = treasure_room_candidates(r, c)
+                    .into_iter()
+                    .find(|&(r, c)| self.is_treasure_room(r, c))?;
+                let entrance = room_outside_coords(top, left)
+                    .into_iter()
+                    .find(|&(r, c)| !matches!(self.at(r, c), Cell::Wall))?;
+                Some(TreasureRoom {
+                    top,
+                    left,
+                    chest: (r, c),
+                    entrance,
+                })
+            })
+            .collect()
+    }
+
+    /// For every chest, the rooms that `maybe_treasure_room` still allows.
+    pub fn treasure_room_candidates(&self) -> Vec<RoomCandidates> {
+        self.chests()
+            .map(|(r, c)| RoomCandidates {
+                chest: (r, c),
+                corners: treasure_room_candidates(r, c)
+                    .into_iter()
+                    .filter(|&(r, c)| self.maybe_treasure_room(r, c))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn chests(&self) -> impl Iterator<Item = (Index, Index)> + '_ {
+        (0..BOARD_SIZE as Index)
+            .flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Chest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A room with its chest in the middle of the left column, opening downwards
+    fn board() -> Board {
+        Board::from_string(
+            " ????????\n?########\n?#...####\n?#C..####\n?#...####\n\
+             ?##.#####\n?##.#####\n?########\n?########",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn finds_rooms_and_their_entrance() {
+        let rooms = board().treasure_rooms();
+        assert_eq!(
+            rooms,
+            [TreasureRoom {
+                top: 1,
+                left: 1,
+                chest: (2, 1),
+                entrance: (4, 2),
+            }]
+        );
+        let room = rooms[0];
+        assert!(room.contains(1, 1) && room.contains(3, 3));
+        assert!(!room.contains(4, 2) && !room.contains(1, 4));
+        assert_eq!(room.cells().len(), 9);
+        assert!(room.cells().iter().all(|&(r, c)| room.contains(r, c)));
+    }
+
+    #[test]
+    fn rooms_need_a_single_entrance() {
+        let mut board = board();
+        board.cells[2][4] = Cell::Empty;
+        assert_eq!(board.treasure_rooms(), []);
+        board.cells[2][4] = Cell::Wall;
+        board.cells[4][2] = Cell::Wall;
+        assert_eq!(board.treasure_rooms(), []);
+    }
+
+    #[test]
+    fn candidates_shrink_as_cells_are_filled_in() {
+        let mut board = Board::from_string(
+            " ????????\n?        \n?        \n?  C     \n?        \n\
+             ?        \n?        \n?        \n?       C",
+        )
+        .unwrap();
+        let candidates = board.treasure_room_candidates();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].chest, (2, 2));
+        assert_eq!(candidates[0].corners.len(), 9);
+        // Only one room fits in the corner
+        assert_eq!(
+            candidates[1],
+            RoomCandidates {
+                chest: (7, 7),
+                corners: vec![(5, 5)],
+            }
+        );
+
+        board.cells[1][1] = Cell::Wall;
+        assert_eq!(
+            board.treasure_room_candidates()[0].corners,
+            [(0, 2), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+    }
+}
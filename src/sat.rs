@@ -0,0 +1,176 @@
+//! A small DPLL SAT solver with two watched literals per clause, for [`crate::solver::Sat`].
+//!
+//! There is no clause learning: the puzzles give at most a few hundred variables, and
+//! unit propagation over the row and column counts does most of the work.
+
+/// A variable and its polarity: `2 * var` for "var is true", `2 * var + 1` for "false".
+pub(crate) type Lit = usize;
+
+pub(crate) fn lit(var: usize, value: bool) -> Lit {
+    2 * var + !value as usize
+}
+
+fn negated(l: Lit) -> Lit {
+    l ^ 1
+}
+
+// The value that makes `l` true.
+fn polarity(l: Lit) -> bool {
+    l & 1 == 0
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Cnf {
+    vars: usize,
+    clauses: Vec<Vec<Lit>>,
+    // Clauses of one literal, which need no watches
+    units: Vec<Lit>,
+    // Whether an empty clause was added
+    empty: bool,
+    // For each literal, the clauses watching it: those with it as their first or second
+    // literal
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    trail: Vec<Lit>,
+    pub decisions: u64,
+    pub conflicts: u64,
+}
+
+impl Cnf {
+    pub fn new_var(&mut self) -> usize {
+        self.vars += 1;
+        self.watches.extend([Vec::new(), Vec::new()]);
+        self.vars - 1
+    }
+
+    pub fn add_clause(&mut self, mut clause: Vec<Lit>) {
+        clause.sort_unstable();
+        clause.dedup();
+        // Always true
+        if clause.windows(2).any(|w| w[1] == negated(w[0])) {
+            return;
+        }
+        match clause[..] {
+            [] => self.empty = true,
+            [l] => self.units.push(l),
+            [first, second, ..] => {
+                let i = self.clauses.len();
+                self.watches[first].push(i);
+                self.watches[second].push(i);
+                self.clauses.push(clause);
+            }
+        }
+    }
+
+    /// Finds an assignment satisfying every clause, trying `prefer` first for each
+    /// variable it decides on.
+    pub fn solve(&mut self, prefer: bool) -> Option<Vec<bool>> {
+        self.values = vec![None; self.vars];
+        self.trail.clear();
+        if self.empty {
+            return None;
+        }
+        for i in 0..self.units.len() {
+            if !self.assign(self.units[i]) {
+                return None;
+            }
+        }
+
+        // The trail length before each decision, the decision, and whether it is the
+        // second value tried
+        let mut decisions: Vec<(usize, Lit, bool)> = Vec::new();
+        let mut queue = 0;
+        loop {
+            if !self.propagate(&mut queue) {
+                self.conflicts += 1;
+                loop {
+                    let (len, l, flipped) = decisions.pop()?;
+                    for undone in self.trail.drain(len..) {
+                        self.values[undone / 2] = None;
+                    }
+                    queue = len;
+                    if !flipped {
+                        decisions.push((len, negated(l), true));
+                        self.assign(negated(l));
+                        break;
+                    }
+                }
+                continue;
+            }
+            match self.values.iter().position(Option::is_none) {
+                Some(var) => {
+                    self.decisions += 1;
+                    let l = lit(var, prefer);
+                    decisions.push((self.trail.len(), l, false));
+                    self.assign(l);
+                }
+                None => return Some(self.values.iter().map(|v| v.unwrap()).collect()),
+            }
+        }
+    }
+
+    fn value(&self, l: Lit) -> Option<bool> {
+        self.values[l / 2].map(|v| v == polarity(l))
+    }
+
+    // Makes `l` true; false if it already is false.
+    fn assign(&mut self, l: Lit) -> bool {
+        match self.value(l) {
+            Some(v) => v,
+            None => {
+                self.values[l / 2] = Some(polarity(l));
+                self.trail.push(l);
+                true
+            }
+        }
+    }
+
+    // Assigns the literals forced by the ones on the trail from `queue` on; false on a
+    // conflict.
+    fn propagate(&mut self, queue: &mut usize) -> bool {
+        while *queue < self.trail.len() {
+            let falsified = negated(self.trail[*queue]);
+            *queue += 1;
+            let watching = std::mem::take(&mut self.watches[falsified]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = false;
+            for (n, &i) in watching.iter().enumerate() {
+                if conflict {
+                    kept.extend_from_slice(&watching[n..]);
+                    break;
+                }
+                let clause = &mut self.clauses[i];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let other = clause[0];
+                if self.values[other / 2].is_some_and(|v| v == polarity(other)) {
+                    kept.push(i);
+                    continue;
+                }
+                let replacement = (2..clause.len()).find(|&k| {
+                    let l = clause[k];
+                    self.values[l / 2].is_none_or(|v| v == polarity(l))
+                });
+                match replacement {
+                    Some(k) => {
+                        clause.swap(1, k);
+                        let watch = clause[1];
+                        self.watches[watch].push(i);
+                    }
+                    None => {
+                        kept.push(i);
+                        if !self.assign(other) {
+                            conflict = true;
+                        }
+                    }
+                }
+            }
+            self.watches[falsified] = kept;
+            if conflict {
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,811 @@
+//! The backtracking search and the heuristics that steer it.
+
+use std::ops::AddAssign;
+
+use crate::{
+    zobrist::TranspositionTable, Board, BoardError, Cell, Index, ParseError, Unsolvable, BOARD_SIZE,
+};
+
+/// Which `Unknown` cell to branch on next.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum VariableOrder {
+    /// The first `Unknown` cell, row by row.
+    #[default]
+    ReadingOrder,
+    /// The cell with the most decided neighbours.
+    MostConstrained,
+    /// Cells next to a monster or close to a chest first.
+    NearClues,
+    /// A cell in the row or column closest to being decided by its count.
+    LeastSlack,
+}
+
+impl VariableOrder {
+    pub const ALL: [VariableOrder; 4] = [
+        VariableOrder::ReadingOrder,
+        VariableOrder::MostConstrained,
+        VariableOrder::NearClues,
+        VariableOrder::LeastSlack,
+    ];
+
+    /// A name that stays the same across versions, as used by [`Search::save`].
+    pub fn name(self) -> &'static str {
+        match self {
+            VariableOrder::ReadingOrder => "reading_order",
+            VariableOrder::MostConstrained => "most_constrained",
+            VariableOrder::NearClues => "near_clues",
+            VariableOrder::LeastSlack => "least_slack",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<VariableOrder> {
+        VariableOrder::ALL
+            .into_iter()
+            .find(|order| order.name() == name)
+    }
+}
+
+/// Which value to try first for the chosen cell.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ValueOrder {
+    #[default]
+    WallFirst,
+    EmptyFirst,
+    /// `Wall` first if the cell's row and column still need walls in at least half of
+    /// their `Unknown` cells.
+    RemainingCount,
+}
+
+impl ValueOrder {
+    pub const ALL: [ValueOrder; 3] = [
+        ValueOrder::WallFirst,
+        ValueOrder::EmptyFirst,
+        ValueOrder::RemainingCount,
+    ];
+
+    /// A name that stays the same across versions, as used by [`Search::save`].
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueOrder::WallFirst => "wall_first",
+            ValueOrder::EmptyFirst => "empty_first",
+            ValueOrder::RemainingCount => "remaining_count",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ValueOrder> {
+        ValueOrder::ALL
+            .into_iter()
+            .find(|order| order.name() == name)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolveOptions {
+    pub variable_order: VariableOrder,
+    pub value_order: ValueOrder,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    /// Search states visited.
+    pub nodes: u64,
+    /// Search states found to be dead ends.
+    pub backtracks: u64,
+    pub solutions: u64,
+    /// Search states whose outcome was found in the transposition table.
+    pub table_hits: u64,
+}
+
+impl AddAssign for SolveStats {
+    fn add_assign(&mut self, other: SolveStats) {
+        self.nodes += other.nodes;
+        self.backtracks += other.backtracks;
+        self.solutions += other.solutions;
+        self.table_hits += other.table_hits;
+    }
+}
+
+/// Search nodes that a series of searches may still visit, for callers that must give up
+/// on hard boards rather than run for as long as they take.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NodeBudget(pub u64);
+
+/// The [`NodeBudget`] ran out before the work was done.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BudgetExceeded;
+
+/// A resumable depth-first search over the `Unknown` cells of a board.
+///
+/// The search is driven one [`Step`] at a time and keeps its own stack of decisions, so it
+/// can be paused, cloned, saved with [`Search::save`] and picked up later with
+/// [`Search::load`]. As an iterator, it yields every solution in turn.
+#[derive(Clone, Debug)]
+pub struct Search {
+    initial: Board,
+    board: Board,
+    options: SolveOptions,
+    stats: SolveStats,
+    stack: Vec<Frame>,
+    phase: Phase,
+    table: Option<TranspositionTable>,
+    // Whether solution counts from the table may stand in for the solutions themselves
+    counting: bool,
+    // Whether forced cells are filled in before branching, rather than only checking that
+    // the board can still be solved
+    propagating: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Phase {
+    // `board` is a new search state that has not been looked at yet
+    Enter,
+    // `board` has been dealt with; the next value of the innermost decision is up
+    Backtrack,
+    Done,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Frame {
+    // The board when the decision was made, after propagation
+    before: [[Cell; BOARD_SIZE]; BOARD_SIZE],
+    cell: (Index, Index),
+    values: [Cell; 2],
+    // How many of `values` have been tried
+    tried: usize,
+    // Hash of `before` and the number of solutions found before the decision, used to
+    // record its number of solutions once all values have been tried
+    table_entry: Option<(u64, u64)>,
+}
+
+/// What happened in one call to [`Search::step`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// `value` is being tried for the cell at (row, col).
+    Decision { row: Index, col: Index, value: Cell },
+    /// The current board cannot be completed.
+    DeadEnd,
+    /// The current board is a solution.
+    Solution,
+    /// The current board has this many solutions, according to the transposition table.
+    Counted(u64),
+    /// There are no more solutions.
+    Exhausted,
+}
+
+impl Search {
+    pub fn new(board: Board, options: SolveOptions) -> Search {
+        Search {
+            initial: board.clone(),
+            board,
+            options,
+            stats: SolveStats::default(),
+            stack: Vec::new(),
+            phase: Phase::Enter,
+            table: None,
+            counting: false,
+            propagating: true,
+        }
+    }
+
+    /// Like [`Search::new`], but without filling in forced cells: every state is only
+    /// checked with [`Board::basic_checks`], as in plain backtracking.
+    pub fn backtracking(board: Board, options: SolveOptions) -> Search {
+        Search {
+            propagating: false,
+            ..Search::new(board, options)
+        }
+    }
+
+    /// Like [`Search::new`], but with a transposition table that remembers how many
+    /// solutions each explored state has, usually filled in by earlier searches.
+    ///
+    /// A single search never reaches the same state twice: any two branches differ in the
+    /// cell they branched on. The table only helps across searches of related boards, such
+    /// as those of [`Board::explain_conflict`] and [`Board::minimize`], which do explore
+    /// the same states; [`Search::into_table`] hands it on to the next one.
+    pub fn with_table(board: Board, options: SolveOptions, table: TranspositionTable) -> Search {
+        Search {
+            table: Some(table),
+            ..Search::new(board, options)
+        }
+    }
+
+    pub fn into_table(self) -> Option<TranspositionTable> {
+        self.table
+    }
+
+    /// The board at the current point of the search.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn stats(&self) -> SolveStats {
+        self.stats
+    }
+
+    /// Number of decisions the current board depends on.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.phase == Phase::Done
+    }
+
+    pub fn step(&mut self) -> Step {
+        match self.phase {
+            Phase::Enter => self.enter(),
+            Phase::Backtrack => self.backtrack(),
+            Phase::Done => Step::Exhausted,
+        }
+    }
+
+    /// Like [`Search::step`], but charges the nodes visited to `budget`, and fails without
+    /// stepping once it is spent.
+    pub fn step_within(&mut self, budget: &mut NodeBudget) -> Result<Step, BudgetExceeded> {
+        if budget.0 == 0 && self.phase != Phase::Done {
+            return Err(BudgetExceeded);
+        }
+        let nodes = self.stats.nodes;
+        let step = self.step();
+        budget.0 = budget.0.saturating_sub(self.stats.nodes - nodes);
+        Ok(step)
+    }
+
+    /// Runs the search up to the next solution.
+    pub fn next_solution(&mut self) -> Option<&Board> {
+        self.next_solution_within(&mut NodeBudget(u64::MAX))
+            .expect("an unlimited budget never runs out")
+    }
+
+    /// Like [`Search::next_solution`], within a budget.
+    pub fn next_solution_within(
+        &mut self,
+        budget: &mut NodeBudget,
+    ) -> Result<Option<&Board>, BudgetExceeded> {
+        loop {
+            match self.step_within(budget)? {
+                Step::Solution => return Ok(Some(&self.board)),
+                Step::Exhausted => return Ok(None),
+                Step::Decision { .. } | Step::DeadEnd | Step::Counted(_) => {}
+            }
+        }
+    }
+
+    /// Counts the remaining solutions, stopping once at least `limit` have been found in
+    /// total.
+    ///
+    /// Unlike [`Search::next_solution`], this can skip over states whose number of solutions
+    /// is already in the transposition table.
+    pub fn count_solutions(&mut self, limit: u64) -> u64 {
+        self.count_solutions_within(limit, &mut NodeBudget(u64::MAX))
+            .expect("an unlimited budget never runs out")
+    }
+
+    /// Like [`Search::count_solutions`], within a budget.
+    pub fn count_solutions_within(
+        &mut self,
+        limit: u64,
+        budget: &mut NodeBudget,
+    ) -> Result<u64, BudgetExceeded> {
+        self.counting = true;
+        let result = loop {
+            if self.stats.solutions >= limit {
+                break Ok(self.stats.solutions);
+            }
+            match self.step_within(budget) {
+                Ok(Step::Exhausted) => break Ok(self.stats.solutions),
+                Ok(_) => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.counting = false;
+        result
+    }
+
+    fn enter(&mut self) -> Step {
+        self.stats.nodes += 1;
+        self.phase = Phase::Backtrack;
+        if self.prune().is_err() {
+            self.stats.backtracks += 1;
+            return Step::DeadEnd;
+        }
+
+        let mut hash = None;
+        if let Some(table) = &self.table {
+            let h = self.board.zobrist_hash();
+            match table.get(h, &self.board) {
+                Some(0) => {
+                    self.stats.table_hits += 1;
+                    self.stats.backtracks += 1;
+                    return Step::DeadEnd;
+                }
+                Some(solutions) if self.counting => {
+                    self.stats.table_hits += 1;
+                    self.stats.solutions += solutions;
+                    return Step::Counted(solutions);
+                }
+                _ => hash = Some(h),
+            }
+        }
+
+        match self.board.branching_cell(self.options.variable_order) {
+            Some((r, c)) => {
+                self.stack.push(Frame {
+                    before: self.board.cells,
+                    cell: (r, c),
+                    values: self.board.value_order(r, c, self.options.value_order),
+                    tried: 0,
+                    table_entry: hash.map(|h| (h, self.stats.solutions)),
+                });
+                self.advance()
+            }
+            None if self.board.check_solved().is_ok() => {
+                self.stats.solutions += 1;
+                Step::Solution
+            }
+            None => {
+                self.stats.backtracks += 1;
+                Step::DeadEnd
+            }
+        }
+    }
+
+    fn backtrack(&mut self) -> Step {
+        while let Some(frame) = self.stack.last() {
+            if frame.tried < frame.values.len() {
+                return self.advance();
+            }
+            if let (Some(table), Some((hash, solutions_before))) =
+                (&mut self.table, frame.table_entry)
+            {
+                let state = Board {
+                    cells: frame.before,
+                    ..self.board.clone()
+                };
+                table.insert(hash, &state, self.stats.solutions - solutions_before);
+            }
+            self.stack.pop();
+        }
+        self.phase = Phase::Done;
+        Step::Exhausted
+    }
+
+    // Fills in forced cells, or only checks the board when not propagating.
+    fn prune(&mut self) -> Result<(), BoardError> {
+        if self.propagating {
+            self.board.propagate()
+        } else {
+            self.board.basic_checks()
+        }
+    }
+
+    // Tries the next value of the innermost decision.
+    fn advance(&mut self) -> Step {
+        let frame = self.stack.last_mut().unwrap();
+        let (row, col) = frame.cell;
+        let value = frame.values[frame.tried];
+        frame.tried += 1;
+        self.board.cells = frame.before;
+        self.board.cells[row as usize][col as usize] = value;
+        self.phase = Phase::Enter;
+        Step::Decision { row, col, value }
+    }
+
+    /// Serializes the search so that it can be resumed with [`Search::load`].
+    ///
+    /// Only the decisions are stored; the boards in between are recomputed on load.
+    /// Cells placed on the initial board, rather than given, are stored next to the puzzle.
+    pub fn save(&self) -> String {
+        let SolveStats {
+            nodes,
+            backtracks,
+            solutions,
+            table_hits,
+        } = self.stats;
+        let mut placed = String::new();
+        for r in 0..BOARD_SIZE as Index {
+            for c in 0..BOARD_SIZE as Index {
+                let cell = self.initial.at(r, c);
+                if !matches!(cell, Cell::Unknown) && !self.initial.is_given(r, c) {
+                    placed += &format!(" {r} {c} {}", cell.name());
+                }
+            }
+        }
+        let mut out = format!(
+            "search 3\npuzzle {}\nplaced{placed}\noptions {} {}{}\nstats {nodes} {backtracks} {solutions} {table_hits}\nphase {}\n",
+            self.initial.to_code(),
+            self.options.variable_order.name(),
+            self.options.value_order.name(),
+            if self.propagating { "" } else { " backtracking" },
+            match self.phase {
+                Phase::Enter => "enter",
+                Phase::Backtrack => "backtrack",
+                Phase::Done => "done",
+            }
+        );
+        for frame in &self.stack {
+            out += &format!(
+                "decision {} {} {} {} {}\n",
+                frame.cell.0,
+                frame.cell.1,
+                frame.values[0].name(),
+                frame.values[1].name(),
+                frame.tried
+            );
+        }
+        out
+    }
+
+    pub fn load(saved: &str) -> Result<Search, ParseError> {
+        let mut lines = saved.lines();
+        let mut field = |name: &str| -> Result<Vec<&str>, ParseError> {
+            let line = lines.next().ok_or(ParseError)?;
+            let mut words = line.split(' ');
+            if words.next() != Some(name) {
+                return Err(ParseError);
+            }
+            Ok(words.collect())
+        };
+
+        if field("search")? != ["3"] {
+            return Err(ParseError);
+        }
+        let [code] = field("puzzle")?[..] else {
+            return Err(ParseError);
+        };
+        let mut initial = Board::from_code(code).map_err(|_| ParseError)?;
+        let placed = field("placed")?;
+        if placed.len() % 3 != 0 {
+            return Err(ParseError);
+        }
+        for cell in placed.chunks(3) {
+            let r = cell[0].parse::<Index>().map_err(|_| ParseError)?;
+            let c = cell[1].parse::<Index>().map_err(|_| ParseError)?;
+            if !initial.is_in_bounds(r, c) || initial.is_given(r, c) {
+                return Err(ParseError);
+            }
+            initial.cells[r as usize][c as usize] = Cell::from_name(cell[2]).ok_or(ParseError)?;
+        }
+        let (variable_order, value_order, propagating) = match field("options")?[..] {
+            [variable, value] => (variable, value, true),
+            [variable, value, "backtracking"] => (variable, value, false),
+            _ => return Err(ParseError),
+        };
+        let options = SolveOptions {
+            variable_order: VariableOrder::from_name(variable_order).ok_or(ParseError)?,
+            value_order: ValueOrder::from_name(value_order).ok_or(ParseError)?,
+        };
+        let stats = match field("stats")?[..] {
+            [nodes, backtracks, solutions, table_hits] => SolveStats {
+                nodes: nodes.parse().map_err(|_| ParseError)?,
+                backtracks: backtracks.parse().map_err(|_| ParseError)?,
+                solutions: solutions.parse().map_err(|_| ParseError)?,
+                table_hits: table_hits.parse().map_err(|_| ParseError)?,
+            },
+            _ => return Err(ParseError),
+        };
+        let phase = match field("phase")?[..] {
+            ["enter"] => Phase::Enter,
+            ["backtrack"] => Phase::Backtrack,
+            ["done"] => Phase::Done,
+            _ => return Err(ParseError),
+        };
+
+        let mut search = Search::new(initial, options);
+        search.propagating = propagating;
+        search.stats = stats;
+        search.phase = phase;
+        for line in lines {
+            let words = line.split(' ').collect::<Vec<_>>();
+            let ["decision", r, c, first, second, tried] = words[..] else {
+                return Err(ParseError);
+            };
+            let (r, c): (Index, Index) = (
+                r.parse().map_err(|_| ParseError)?,
+                c.parse().map_err(|_| ParseError)?,
+            );
+            let values = [
+                Cell::from_name(first).ok_or(ParseError)?,
+                Cell::from_name(second).ok_or(ParseError)?,
+            ];
+            let tried = tried.parse::<usize>().map_err(|_| ParseError)?;
+            if !(1..=values.len()).contains(&tried)
+                || !matches!(
+                    values,
+                    [Cell::Wall, Cell::Empty] | [Cell::Empty, Cell::Wall]
+                )
+            {
+                return Err(ParseError);
+            }
+
+            // Replay the decision on top of the state it was made in
+            if search.prune().is_err() || !matches!(search.board.at(r, c), Cell::Unknown) {
+                return Err(ParseError);
+            }
+            let board = &mut search.board;
+            search.stack.push(Frame {
+                before: board.cells,
+                cell: (r, c),
+                values,
+                tried,
+                // The solutions found before this decision are not known anymore
+                table_entry: None,
+            });
+            board.cells[r as usize][c as usize] = values[tried - 1];
+        }
+        match phase {
+            // Once the search is over, no decisions are left to try
+            Phase::Done if !search.stack.is_empty() => return Err(ParseError),
+            // Without a decision, only the initial board can be up next
+            Phase::Enter if search.stack.is_empty() && stats != SolveStats::default() => {
+                return Err(ParseError)
+            }
+            Phase::Enter => {}
+            // The state the last decision led to had already been looked at
+            _ => {
+                let _ = search.prune();
+            }
+        }
+
+        Ok(search)
+    }
+}
+
+impl Iterator for Search {
+    type Item = Board;
+
+    fn next(&mut self) -> Option<Board> {
+        self.next_solution().cloned()
+    }
+}
+
+impl Board {
+    pub fn solve_with(
+        &mut self,
+        options: &SolveOptions,
+        stats: &mut SolveStats,
+    ) -> Result<(), Unsolvable> {
+        let mut search = Search::new(self.clone(), *options);
+        let solution = search.next_solution().cloned();
+        *stats += search.stats();
+        *self = solution.ok_or(Unsolvable)?;
+        self.reveal_counts();
+        Ok(())
+    }
+
+    /// Counts the solutions, stopping once `limit` of them have been found.
+    pub fn count_solutions(&self, limit: u64) -> u64 {
+        Search::new(self.clone(), SolveOptions::default()).count_solutions(limit)
+    }
+
+    /// The `Unknown` cell to branch on, or `None` if there are none left.
+    pub fn branching_cell(&self, order: VariableOrder) -> Option<(Index, Index)> {
+        let unknown = (0..BOARD_SIZE as Index)
+            .flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Unknown));
+        let neighbors = |r: Index, c: Index| [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)];
+
+        match order {
+            VariableOrder::ReadingOrder => unknown.min(),
+            VariableOrder::MostConstrained => unknown
+                .map(|(r, c)| {
+                    let decided = neighbors(r, c)
+                        .into_iter()
+                        .filter(|&(r, c)| !matches!(self.at(r, c), Cell::Unknown))
+                        .count();
+                    // Prefer the earliest cell among equals
+                    (decided, -r, -c)
+                })
+                .max()
+                .map(|(_, r, c)| (-r, -c)),
+            VariableOrder::NearClues => {
+                let near_clue = |&(r, c): &(Index, Index)| {
+                    let next_to_monster = neighbors(r, c)
+                        .into_iter()
+                        .any(|(r, c)| matches!(self.at(r, c), Cell::Monster));
+                    let near_chest = (-2..=2)
+                        .flat_map(|dr| (-2..=2).map(move |dc| (r + dr, c + dc)))
+                        .any(|(r, c)| matches!(self.at(r, c), Cell::Chest));
+                    next_to_monster || near_chest
+                };
+                unknown.clone().find(near_clue).or_else(|| unknown.min())
+            }
+            VariableOrder::LeastSlack => {
+                let row_slack = |r: Index| {
+                    self.slack(
+                        (0..BOARD_SIZE as Index).map(|c| (r, c)),
+                        self.row_counts[r as usize],
+                    )
+                };
+                let col_slack = |c: Index| {
+                    self.slack(
+                        (0..BOARD_SIZE as Index).map(|r| (r, c)),
+                        self.column_counts[c as usize],
+                    )
+                };
+                unknown.min_by_key(|&(r, c)| row_slack(r).min(col_slack(c)))
+            }
+        }
+    }
+
+    /// The order in which to try values for the `Unknown` cell at (r, c).
+    pub fn value_order(&self, r: Index, c: Index, order: ValueOrder) -> [Cell; 2] {
+        let wall_first = match order {
+            ValueOrder::WallFirst => true,
+            ValueOrder::EmptyFirst => false,
+            ValueOrder::RemainingCount => {
+                let (row_needed, row_unknown) = self.walls_needed(
+                    (0..BOARD_SIZE as Index).map(|j| (r, j)),
+                    self.row_counts[r as usize],
+                );
+                let (col_needed, col_unknown) = self.walls_needed(
+                    (0..BOARD_SIZE as Index).map(|i| (i, c)),
+                    self.column_counts[c as usize],
+                );
+                2 * (row_needed + col_needed) >= row_unknown + col_unknown
+            }
+        };
+        if wall_first {
+            [Cell::Wall, Cell::Empty]
+        } else {
+            [Cell::Empty, Cell::Wall]
+        }
+    }
+
+    // How many of the line's `Unknown` cells still have to be walls, and how many there are.
+    // Lines with a hidden count are treated as needing walls in half their `Unknown` cells.
+    fn walls_needed(
+        &self,
+        line: impl Iterator<Item = (Index, Index)>,
+        count: Option<u8>,
+    ) -> (i32, i32) {
+        let (mut walls, mut unknown) = (0, 0);
+        for (r, c) in line {
+            match self.at(r, c) {
+                Cell::Wall => walls += 1,
+                Cell::Unknown => unknown += 1,
+                _ => {}
+            }
+        }
+        match count {
+            Some(count) => (count as i32 - walls, unknown),
+            None => (unknown / 2, unknown),
+        }
+    }
+
+    // How far a line is from having all its `Unknown` cells decided by its count alone.
+    fn slack(&self, line: impl Iterator<Item = (Index, Index)>, count: Option<u8>) -> i32 {
+        let (needed, unknown) = self.walls_needed(line, count);
+        if unknown == 0 || count.is_none() {
+            return i32::MAX;
+        }
+        needed.min(unknown - needed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Last Call BBS 5-8 with three of its column counts hidden, which has two solutions
+    fn puzzle() -> Board {
+        Board::from_string(
+            " ???44253\n4M   M M \n4        \n2M       \n4       M\n\
+             6M       \n2       M\n3        \n4 M   M M",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn a_table_carries_counts_over_to_the_next_search() {
+        let mut plain = Search::new(puzzle(), SolveOptions::default());
+        assert_eq!(plain.count_solutions(u64::MAX), 2);
+
+        let table = TranspositionTable::new(1 << 10);
+        let mut first = Search::with_table(puzzle(), SolveOptions::default(), table);
+        assert_eq!(first.count_solutions(u64::MAX), 2);
+        assert_eq!(first.stats().table_hits, 0);
+        assert_eq!(first.stats().nodes, plain.stats().nodes);
+
+        let table = first.into_table().unwrap();
+        let mut second = Search::with_table(puzzle(), SolveOptions::default(), table);
+        assert_eq!(second.count_solutions(u64::MAX), 2);
+        assert!(second.stats().table_hits > 0);
+        assert!(second.stats().nodes < plain.stats().nodes);
+    }
+
+    fn all_steps(search: &mut Search) -> Vec<Step> {
+        let mut steps = Vec::new();
+        loop {
+            let step = search.step();
+            steps.push(step);
+            if step == Step::Exhausted {
+                return steps;
+            }
+        }
+    }
+
+    #[test]
+    fn a_loaded_search_carries_on_where_it_was_saved() {
+        let mut placed = puzzle();
+        placed.cells[6][0] = Cell::Wall;
+        let options = SolveOptions {
+            variable_order: VariableOrder::LeastSlack,
+            value_order: ValueOrder::EmptyFirst,
+        };
+        let searches = [
+            Search::new(puzzle(), SolveOptions::default()),
+            Search::new(puzzle(), options),
+            Search::new(placed, SolveOptions::default()),
+            Search::backtracking(puzzle(), SolveOptions::default()),
+        ];
+        for search in searches {
+            let mut finished = search.clone();
+            let expected = all_steps(&mut finished);
+            for n in [
+                0,
+                1,
+                2,
+                10,
+                expected.len() / 2,
+                expected.len() - 1,
+                expected.len(),
+            ] {
+                let mut interrupted = search.clone();
+                for _ in 0..n {
+                    interrupted.step();
+                }
+                let saved = interrupted.save();
+                let mut loaded = Search::load(&saved).unwrap();
+                assert_eq!(loaded.save(), saved, "after {n} steps");
+                if !interrupted.is_exhausted() {
+                    assert_eq!(loaded.board(), interrupted.board(), "after {n} steps");
+                }
+
+                let rest = all_steps(&mut loaded);
+                let skipped = n.min(expected.len() - 1);
+                assert_eq!(rest[..], expected[skipped..], "after {n} steps");
+                assert_eq!(loaded.stats(), finished.stats(), "after {n} steps");
+            }
+        }
+    }
+
+    #[test]
+    fn load_rejects_inconsistent_searches() {
+        let mut search = Search::new(puzzle(), SolveOptions::default());
+        for _ in 0..10 {
+            search.step();
+        }
+        let saved = search.save();
+        assert!(saved.contains("\noptions reading_order wall_first\n"));
+        assert!(saved.contains("\nphase enter\n"));
+        assert!(Search::load(&saved).is_ok());
+
+        let first_decision = saved.lines().find(|l| l.starts_with("decision")).unwrap();
+        let (r, c) = {
+            let words = first_decision.split(' ').collect::<Vec<_>>();
+            (words[1], words[2])
+        };
+        let tampered = [
+            // Names that depend on how the variants are spelled in Rust
+            saved.replace("reading_order wall_first", "ReadingOrder WallFirst"),
+            // Over, with decisions left to try
+            saved.replace("phase enter", "phase done"),
+            // The same value twice, or a value that is not tried at all
+            saved.replace(first_decision, &format!("decision {r} {c} wall wall 1")),
+            saved.replace(first_decision, &format!("decision {r} {c} wall empty 0")),
+            saved.replace(first_decision, &format!("decision {r} {c} wall empty 3")),
+            // About to look at the initial board, after having done some work
+            saved
+                .lines()
+                .filter(|l| !l.starts_with("decision"))
+                .map(|l| format!("{l}\n"))
+                .collect(),
+            saved.replace("search 3", "search 2"),
+        ];
+        for tampered in tampered {
+            assert!(Search::load(&tampered).is_err(), "{tampered}");
+        }
+    }
+}
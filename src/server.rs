@@ -0,0 +1,218 @@
+//! A small HTTP server answering JSON requests, for front-ends that cannot link the
+//! library directly.
+//!
+//! The endpoints of [`crate::api`] are served at `/parse`, `/solve` and so on, as `POST`
+//! requests with a JSON body. Errors come with a non-2xx status and an `error` message.
+//! Connections are handled one at a time and closed after the response; a client that
+//! does not send its whole request within the request timeout gets a 408, so that a slow
+//! client cannot hold up the others for longer than that. A request that makes the
+//! library panic gets a 500 and does not bring the server down.
+
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+use crate::api::{self, error, ApiError};
+
+#[derive(Copy, Clone, Debug)]
+pub struct ServerOptions {
+    /// Largest accepted request, headers included.
+    pub max_request_bytes: usize,
+    /// Search nodes a single request may use.
+    pub node_budget: u64,
+    /// Time a client has to send its whole request, from the moment it is accepted.
+    pub request_timeout: Duration,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        ServerOptions {
+            max_request_bytes: 64 * 1024,
+            node_budget: 100_000,
+            request_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+pub struct Server {
+    listener: TcpListener,
+    options: ServerOptions,
+}
+
+// An HTTP status and the message sent along with it.
+type Failure = (u16, String);
+
+impl Server {
+    /// Binds to `addr`; use port 0 to let the system pick a free port.
+    pub fn bind(addr: impl ToSocketAddrs, options: ServerOptions) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr)?,
+            options,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves requests until the listener fails.
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            // A misbehaving client only loses its own connection
+            let stream = stream?;
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| self.handle(stream)));
+        }
+        Ok(())
+    }
+
+    /// Reads one request from `stream` and answers it.
+    pub fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        let (status, body) = match self.read_request(&mut stream) {
+            Ok(None) => (204, None),
+            Ok(Some((path, body))) => {
+                let endpoint = path.strip_prefix('/').unwrap_or(&path);
+                let answer =
+                    panic::catch_unwind(|| api::call(endpoint, &body, self.options.node_budget));
+                match answer {
+                    Ok(Ok(json)) => (200, Some(json)),
+                    Ok(Err(e)) => {
+                        let status = match e {
+                            ApiError::BadRequest(_) => 400,
+                            ApiError::UnknownEndpoint(_) => 404,
+                            ApiError::Failed(_) => 422,
+                        };
+                        (status, Some(error(&e.message())))
+                    }
+                    Err(_) => (500, Some(error("internal error"))),
+                }
+            }
+            Err((status, message)) => (status, Some(error(&message))),
+        };
+        let body = body.map_or(String::new(), |json| json.to_string());
+        write!(
+            stream,
+            "HTTP/1.1 {status} {}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Access-Control-Allow-Origin: *\r\n\
+             Access-Control-Allow-Methods: POST, OPTIONS\r\n\
+             Access-Control-Allow-Headers: Content-Type\r\n\
+             Connection: close\r\n\r\n{body}",
+            reason(status),
+            body.len()
+        )?;
+        stream.flush()
+    }
+
+    // The path and body of a `POST`, or `None` for a CORS preflight.
+    fn read_request(&self, stream: &mut TcpStream) -> Result<Option<(String, String)>, Failure> {
+        let limit = self.options.max_request_bytes;
+        let too_large = || (413, format!("requests are limited to {limit} bytes"));
+        let bad_request = |message: &str| (400, message.to_string());
+
+        let deadline = Instant::now() + self.options.request_timeout;
+        let mut buf = Vec::new();
+        let header_end = loop {
+            if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                if end + 4 > limit {
+                    return Err(too_large());
+                }
+                break end + 4;
+            }
+            if buf.len() > limit {
+                return Err(too_large());
+            }
+            read_before(stream, deadline, &mut buf)?;
+        };
+
+        let head =
+            std::str::from_utf8(&buf[..header_end]).map_err(|_| bad_request("invalid header"))?;
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let (method, path) = (
+            request_line.next().unwrap_or_default().to_string(),
+            request_line.next().unwrap_or_default().to_string(),
+        );
+        let mut content_length = None;
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = Some(
+                        value
+                            .trim()
+                            .parse::<usize>()
+                            .map_err(|_| bad_request("invalid Content-Length"))?,
+                    );
+                }
+            }
+        }
+
+        match method.as_str() {
+            "OPTIONS" => return Ok(None),
+            "POST" => {}
+            _ => return Err((405, "only POST is supported".to_string())),
+        }
+        let length = content_length.ok_or((411, "Content-Length is required".to_string()))?;
+        if length > limit.saturating_sub(header_end) {
+            return Err(too_large());
+        }
+        while buf.len() < header_end + length {
+            read_before(stream, deadline, &mut buf)?;
+        }
+        let body = String::from_utf8(buf[header_end..header_end + length].to_vec())
+            .map_err(|_| bad_request("the body is not UTF-8"))?;
+        Ok(Some((path, body)))
+    }
+}
+
+// Reads what the client has sent so far into `buf`, waiting no later than `deadline`.
+fn read_before(
+    stream: &mut TcpStream,
+    deadline: Instant,
+    buf: &mut Vec<u8>,
+) -> Result<(), Failure> {
+    let timed_out = || (408, "the request took too long to arrive".to_string());
+    let left = deadline.saturating_duration_since(Instant::now());
+    if left.is_zero() {
+        return Err(timed_out());
+    }
+    let mut chunk = [0; 4096];
+    let read = stream
+        .set_read_timeout(Some(left))
+        .and_then(|()| stream.read(&mut chunk));
+    match read {
+        Ok(0) => Err((400, "incomplete request".to_string())),
+        Ok(n) => {
+            buf.extend_from_slice(&chunk[..n]);
+            Ok(())
+        }
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            Err(timed_out())
+        }
+        Err(_) => Err((400, "incomplete request".to_string())),
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Content Too Large",
+        422 => "Unprocessable Content",
+        500 => "Internal Server Error",
+        _ => "Error",
+    }
+}
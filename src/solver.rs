@@ -0,0 +1,731 @@
+//! Interchangeable solving strategies behind one [`Solver`] trait.
+//!
+//! ```
+//! use zach_dnd_solver::{search::SolveOptions, solver::SolverKind, Board};
+//!
+//! let puzzle = Board::from_string(
+//!     " 35344253\n4M   M M \n4        \n2M       \n4       M\n\
+//!      6M       \n2       M\n3        \n4 M   M M",
+//! )
+//! .unwrap();
+//! for kind in SolverKind::ALL {
+//!     let mut solver = kind.solver(SolveOptions::default());
+//!     assert_eq!(solver.count(&puzzle, 2), 1);
+//! }
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc, Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    room_inside_coords, room_outside_coords,
+    sat::{lit, Cnf, Lit},
+    search::{Search, SolveOptions, SolveStats, Step, ValueOrder},
+    wide_corridor_exemptions, Board, Cell, Index, Unsolvable, BOARD_SIZE,
+};
+
+pub trait Solver {
+    /// Finds a solution, with the hidden counts filled in.
+    fn solve(&mut self, board: &Board) -> Result<Board, Unsolvable>;
+
+    /// Calls `visit` with each solution in turn, until it returns `false`.
+    fn enumerate(&mut self, board: &Board, visit: &mut dyn FnMut(&Board) -> bool);
+
+    /// Counts the solutions, stopping once `limit` of them have been found.
+    fn count(&mut self, board: &Board, limit: u64) -> u64 {
+        let mut count = 0;
+        if limit > 0 {
+            self.enumerate(board, &mut |_| {
+                count += 1;
+                count < limit
+            });
+        }
+        count
+    }
+
+    /// The work done by all calls so far.
+    fn stats(&self) -> SolveStats;
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SolverKind {
+    /// Branching with only [`Board::basic_checks`] to cut off dead ends.
+    Backtracking,
+    /// Branching on top of the deductions of [`Board::propagate`], as [`Board::solve`] does.
+    #[default]
+    Propagation,
+    /// A SAT encoding of the rules, with connectivity checked on each candidate.
+    Sat,
+    /// [`SolverKind::Propagation`] with the top of the search tree split across threads.
+    Parallel,
+}
+
+impl SolverKind {
+    pub const ALL: [SolverKind; 4] = [
+        SolverKind::Backtracking,
+        SolverKind::Propagation,
+        SolverKind::Sat,
+        SolverKind::Parallel,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SolverKind::Backtracking => "backtracking",
+            SolverKind::Propagation => "propagation",
+            SolverKind::Sat => "sat",
+            SolverKind::Parallel => "parallel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<SolverKind> {
+        SolverKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn solver(self, options: SolveOptions) -> Box<dyn Solver + Send> {
+        match self {
+            SolverKind::Backtracking => Box::new(Backtracking::new(options)),
+            SolverKind::Propagation => Box::new(Propagation::new(options)),
+            SolverKind::Sat => Box::new(Sat::new(options)),
+            SolverKind::Parallel => Box::new(Parallel::new(options)),
+        }
+    }
+}
+
+// Solving, enumerating and counting with a search, for the solvers built on one.
+fn search_solve(search: Search, stats: &mut SolveStats) -> Result<Board, Unsolvable> {
+    let mut search = search;
+    let solution = search.next_solution().cloned();
+    *stats += search.stats();
+    let mut solution = solution.ok_or(Unsolvable)?;
+    solution.reveal_counts();
+    Ok(solution)
+}
+
+fn search_enumerate(search: Search, stats: &mut SolveStats, visit: &mut dyn FnMut(&Board) -> bool) {
+    let mut search = search;
+    while let Some(solution) = search.next_solution() {
+        if !visit(solution) {
+            break;
+        }
+    }
+    *stats += search.stats();
+}
+
+fn search_count(search: Search, stats: &mut SolveStats, limit: u64) -> u64 {
+    let mut search = search;
+    let count = search.count_solutions(limit);
+    *stats += search.stats();
+    count
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Backtracking {
+    options: SolveOptions,
+    stats: SolveStats,
+}
+
+impl Backtracking {
+    pub fn new(options: SolveOptions) -> Backtracking {
+        Backtracking {
+            options,
+            stats: SolveStats::default(),
+        }
+    }
+}
+
+impl Solver for Backtracking {
+    fn solve(&mut self, board: &Board) -> Result<Board, Unsolvable> {
+        search_solve(
+            Search::backtracking(board.clone(), self.options),
+            &mut self.stats,
+        )
+    }
+
+    fn enumerate(&mut self, board: &Board, visit: &mut dyn FnMut(&Board) -> bool) {
+        let search = Search::backtracking(board.clone(), self.options);
+        search_enumerate(search, &mut self.stats, visit);
+    }
+
+    fn count(&mut self, board: &Board, limit: u64) -> u64 {
+        let search = Search::backtracking(board.clone(), self.options);
+        search_count(search, &mut self.stats, limit)
+    }
+
+    fn stats(&self) -> SolveStats {
+        self.stats
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Propagation {
+    options: SolveOptions,
+    stats: SolveStats,
+}
+
+impl Propagation {
+    pub fn new(options: SolveOptions) -> Propagation {
+        Propagation {
+            options,
+            stats: SolveStats::default(),
+        }
+    }
+}
+
+impl Solver for Propagation {
+    fn solve(&mut self, board: &Board) -> Result<Board, Unsolvable> {
+        search_solve(Search::new(board.clone(), self.options), &mut self.stats)
+    }
+
+    fn enumerate(&mut self, board: &Board, visit: &mut dyn FnMut(&Board) -> bool) {
+        let search = Search::new(board.clone(), self.options);
+        search_enumerate(search, &mut self.stats, visit);
+    }
+
+    fn count(&mut self, board: &Board, limit: u64) -> u64 {
+        let search = Search::new(board.clone(), self.options);
+        search_count(search, &mut self.stats, limit)
+    }
+
+    fn stats(&self) -> SolveStats {
+        self.stats
+    }
+}
+
+/// Solves a propositional encoding of the board: one variable per cell, true for a wall,
+/// and one per possible treasure room.
+///
+/// The counts, monsters, dead ends, treasure rooms and corridor widths are all encoded
+/// as clauses. Connectivity is not; each candidate is checked with
+/// [`Board::check_solved`] instead, and ruled out by a new clause if it fails. The
+/// variable order is ignored, and the value order only decides whether walls or floor
+/// are tried first.
+#[derive(Clone, Debug, Default)]
+pub struct Sat {
+    options: SolveOptions,
+    stats: SolveStats,
+}
+
+impl Sat {
+    pub fn new(options: SolveOptions) -> Sat {
+        Sat {
+            options,
+            stats: SolveStats::default(),
+        }
+    }
+}
+
+impl Solver for Sat {
+    fn solve(&mut self, board: &Board) -> Result<Board, Unsolvable> {
+        let mut solution = None;
+        self.enumerate(board, &mut |found| {
+            solution = Some(found.clone());
+            false
+        });
+        let mut solution = solution.ok_or(Unsolvable)?;
+        solution.reveal_counts();
+        Ok(solution)
+    }
+
+    fn enumerate(&mut self, board: &Board, visit: &mut dyn FnMut(&Board) -> bool) {
+        let mut cnf = encode(board);
+        let prefer_walls = self.options.value_order != ValueOrder::EmptyFirst;
+        while let Some(model) = cnf.solve(prefer_walls) {
+            let mut candidate = board.clone();
+            for (r, row) in candidate.cells.iter_mut().enumerate() {
+                for (c, cell) in row.iter_mut().enumerate() {
+                    if *cell == Cell::Unknown {
+                        *cell = if model[cell_var(r, c)] {
+                            Cell::Wall
+                        } else {
+                            Cell::Empty
+                        };
+                    }
+                }
+            }
+            self.stats.nodes += 1;
+            let solved = candidate.check_solved().is_ok();
+            if !solved {
+                self.stats.backtracks += 1;
+                let cut = disconnection_cut(board, &candidate);
+                cnf.add_clause(cut.unwrap_or_else(|| blocking_clause(board, &candidate)));
+                continue;
+            }
+            self.stats.solutions += 1;
+            if !visit(&candidate) {
+                break;
+            }
+            cnf.add_clause(blocking_clause(board, &candidate));
+        }
+        self.stats.nodes += cnf.decisions;
+        self.stats.backtracks += cnf.conflicts;
+    }
+
+    fn stats(&self) -> SolveStats {
+        self.stats
+    }
+}
+
+fn cell_var(r: usize, c: usize) -> usize {
+    r * BOARD_SIZE + c
+}
+
+fn wall(r: Index, c: Index, value: bool) -> Lit {
+    lit(cell_var(r as usize, c as usize), value)
+}
+
+fn in_bounds(cells: impl IntoIterator<Item = (Index, Index)>) -> Vec<(Index, Index)> {
+    let bounds = 0..BOARD_SIZE as Index;
+    cells
+        .into_iter()
+        .filter(|(r, c)| bounds.contains(r) && bounds.contains(c))
+        .collect()
+}
+
+// Clauses saying that exactly one of `lits` is true, each weakened by `unless`.
+fn exactly_one(cnf: &mut Cnf, lits: &[Lit], unless: &[Lit]) {
+    cnf.add_clause(lits.iter().chain(unless).copied().collect());
+    for (i, &a) in lits.iter().enumerate() {
+        for &b in &lits[i + 1..] {
+            let mut clause = vec![a ^ 1, b ^ 1];
+            clause.extend_from_slice(unless);
+            cnf.add_clause(clause);
+        }
+    }
+}
+
+// Every solution of the board satisfies the clauses, but not every assignment
+// satisfying them is a solution.
+fn encode(board: &Board) -> Cnf {
+    let mut cnf = Cnf::default();
+    for _ in 0..BOARD_SIZE * BOARD_SIZE {
+        cnf.new_var();
+    }
+    let all = (0..BOARD_SIZE as Index).flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)));
+
+    for (r, c) in all.clone() {
+        match board.at(r, c) {
+            Cell::Unknown => {}
+            cell => cnf.add_clause(vec![wall(r, c, cell == Cell::Wall)]),
+        }
+    }
+
+    // Counts: no `count + 1` cells of the line are all walls, and no `len - count + 1`
+    // are all floor
+    let lines = (0..BOARD_SIZE).flat_map(|i| {
+        let row = (0..BOARD_SIZE).map(move |j| (i, j)).collect::<Vec<_>>();
+        let column = (0..BOARD_SIZE).map(move |j| (j, i)).collect::<Vec<_>>();
+        [(board.row_counts[i], row), (board.column_counts[i], column)]
+    });
+    for (count, cells) in lines {
+        let Some(count) = count else { continue };
+        let count = count as usize;
+        if count > cells.len() {
+            cnf.add_clause(Vec::new());
+            continue;
+        }
+        for (size, value) in [(count + 1, false), (cells.len() + 1 - count, true)] {
+            for subset in subsets(cells.len(), size) {
+                cnf.add_clause(
+                    subset
+                        .into_iter()
+                        .map(|k| lit(cell_var(cells[k].0, cells[k].1), value))
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    // Monsters have exactly one floor neighbour; other floor cells do not
+    for (r, c) in all.clone() {
+        let neighbours = in_bounds([(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)]);
+        match board.at(r, c) {
+            Cell::Monster => {
+                let floor = neighbours.iter().map(|&(r, c)| wall(r, c, false));
+                exactly_one(&mut cnf, &floor.collect::<Vec<_>>(), &[]);
+            }
+            Cell::Wall => {}
+            _ => {
+                for &(nr, nc) in &neighbours {
+                    let mut clause = vec![wall(r, c, true), wall(nr, nc, true)];
+                    clause.extend(
+                        neighbours
+                            .iter()
+                            .filter(|&&n| n != (nr, nc))
+                            .map(|&(r, c)| wall(r, c, false)),
+                    );
+                    cnf.add_clause(clause);
+                }
+            }
+        }
+    }
+
+    // Treasure rooms: 3x3 floor around exactly one chest, with one way in
+    let mut rooms = Vec::new();
+    let last = (BOARD_SIZE - 3) as Index;
+    for (top, left) in (0..=last).flat_map(|r| (0..=last).map(move |c| (r, c))) {
+        let inside = room_inside_coords(top, left);
+        let outside = in_bounds(room_outside_coords(top, left));
+        let chests = inside
+            .iter()
+            .filter(|&&(r, c)| board.at(r, c) == Cell::Chest)
+            .count();
+        let blocked = inside
+            .iter()
+            .any(|&(r, c)| matches!(board.at(r, c), Cell::Wall | Cell::Monster));
+        if chests != 1 || blocked || outside.is_empty() {
+            continue;
+        }
+        let room = cnf.new_var();
+        for (r, c) in inside {
+            cnf.add_clause(vec![lit(room, false), wall(r, c, false)]);
+        }
+        let entrances = outside
+            .iter()
+            .map(|&(r, c)| wall(r, c, false))
+            .collect::<Vec<_>>();
+        exactly_one(&mut cnf, &entrances, &[lit(room, false)]);
+        rooms.push(((top, left), room));
+    }
+    for (r, c) in all.clone() {
+        if board.at(r, c) == Cell::Chest {
+            cnf.add_clause(
+                rooms
+                    .iter()
+                    .filter(|((top, left), _)| {
+                        (*top..top + 3).contains(&r) && (*left..left + 3).contains(&c)
+                    })
+                    .map(|&(_, room)| lit(room, true))
+                    .collect(),
+            );
+        }
+    }
+
+    // No 2x2 floor outside the surroundings of a treasure room
+    for (r, c) in all.filter(|&(r, c)| r < last + 2 && c < last + 2) {
+        let block = [(r, c), (r, c + 1), (r + 1, c), (r + 1, c + 1)];
+        if block
+            .iter()
+            .any(|&(r, c)| !matches!(board.at(r, c), Cell::Unknown | Cell::Empty))
+        {
+            continue;
+        }
+        let mut clause = block
+            .iter()
+            .map(|&(r, c)| wall(r, c, true))
+            .collect::<Vec<_>>();
+        for &((top, left), room) in &rooms {
+            if wide_corridor_exemptions(top, left).any(|cell| cell == (r, c)) {
+                clause.push(lit(room, true));
+            }
+        }
+        cnf.add_clause(clause);
+    }
+    cnf
+}
+
+// Every `size`-element subset of `0..n`.
+fn subsets(n: usize, size: usize) -> Vec<Vec<usize>> {
+    if size > n {
+        return Vec::new();
+    }
+    let mut all = Vec::new();
+    let mut subset = (0..size).collect::<Vec<_>>();
+    loop {
+        all.push(subset.clone());
+        // The last position that can still move right
+        let Some(i) = (0..size).rev().find(|&i| subset[i] < n - size + i) else {
+            return all;
+        };
+        subset[i] += 1;
+        for j in i + 1..size {
+            subset[j] = subset[j - 1] + 1;
+        }
+    }
+}
+
+// Rules out exactly this filling of the `Unknown` cells of `board`.
+fn blocking_clause(board: &Board, candidate: &Board) -> Vec<Lit> {
+    (0..BOARD_SIZE as Index)
+        .flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+        .filter(|&(r, c)| board.at(r, c) == Cell::Unknown)
+        .map(|(r, c)| wall(r, c, candidate.at(r, c) != Cell::Wall))
+        .collect()
+}
+
+// If the floor of `candidate` falls apart, a clause ruling out every board in which one
+// of its pieces is closed off the same way: a piece missing some floor cell of the puzzle
+// cannot be all the floor there is.
+fn disconnection_cut(board: &Board, candidate: &Board) -> Option<Vec<Lit>> {
+    let mut piece = [[usize::MAX; BOARD_SIZE]; BOARD_SIZE];
+    let mut pieces = 0;
+    for (r, c) in
+        (0..BOARD_SIZE as Index).flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+    {
+        if candidate.at(r, c) == Cell::Wall || piece[r as usize][c as usize] != usize::MAX {
+            continue;
+        }
+        let mut stack = vec![(r, c)];
+        while let Some((r, c)) = stack.pop() {
+            if candidate.at(r, c) == Cell::Wall || piece[r as usize][c as usize] != usize::MAX {
+                continue;
+            }
+            piece[r as usize][c as usize] = pieces;
+            stack.extend([(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)]);
+        }
+        pieces += 1;
+    }
+    if pieces < 2 {
+        return None;
+    }
+
+    // A floor cell of the puzzle, and a piece it is not part of
+    let (fr, fc) = (0..BOARD_SIZE)
+        .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
+        .find(|&(r, c)| !matches!(board.cells[r][c], Cell::Unknown | Cell::Wall))?;
+    let cut = (piece[fr][fc] + 1) % pieces;
+
+    let mut clause = Vec::new();
+    for (r, c) in
+        (0..BOARD_SIZE as Index).flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)))
+    {
+        if board.at(r, c) != Cell::Unknown {
+            continue;
+        }
+        let neighbours = [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)];
+        let in_cut = |(r, c): (Index, Index)| {
+            candidate.is_in_bounds(r, c) && piece[r as usize][c as usize] == cut
+        };
+        if in_cut((r, c)) {
+            // The piece could lose this cell
+            clause.push(wall(r, c, true));
+        } else if neighbours.into_iter().any(in_cut) {
+            // Or grow through this wall
+            clause.push(wall(r, c, false));
+        }
+    }
+    Some(clause)
+}
+
+// Solutions held on to for each branch ahead of the one being visited
+const BUFFERED_SOLUTIONS: usize = 16;
+
+/// [`Propagation`] with the first few decisions made up front, and the resulting
+/// branches searched on separate threads.
+///
+/// Solutions come in the same order as from a single search with the same options.
+/// [`Solver::enumerate`] visits them as they are found, while the threads searching later
+/// branches hold on to only a few each.
+#[derive(Clone, Debug)]
+pub struct Parallel {
+    options: SolveOptions,
+    threads: usize,
+    stats: SolveStats,
+}
+
+impl Parallel {
+    /// Uses one thread per available core.
+    pub fn new(options: SolveOptions) -> Parallel {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Parallel::with_threads(options, threads)
+    }
+
+    /// Uses `threads` threads, or just the calling thread on targets that cannot start
+    /// threads at all, like WebAssembly.
+    pub fn with_threads(options: SolveOptions, threads: usize) -> Parallel {
+        let threads = if cfg!(target_family = "wasm") {
+            1
+        } else {
+            threads.max(1)
+        };
+        Parallel {
+            options,
+            threads,
+            stats: SolveStats::default(),
+        }
+    }
+
+    // The boards after the first decisions, in the order a single search would visit
+    // them, with enough of them to keep every thread busy.
+    fn branches(&mut self, board: &Board) -> Vec<Board> {
+        let mut branches = vec![board.clone()];
+        while branches.len() < 4 * self.threads {
+            let mut next = Vec::new();
+            let mut split = false;
+            for mut branch in branches {
+                self.stats.nodes += 1;
+                if branch.propagate().is_err() {
+                    self.stats.backtracks += 1;
+                    continue;
+                }
+                match branch.branching_cell(self.options.variable_order) {
+                    Some((r, c)) => {
+                        split = true;
+                        for value in branch.value_order(r, c, self.options.value_order) {
+                            let mut child = branch.clone();
+                            child.cells[r as usize][c as usize] = value;
+                            next.push(child);
+                        }
+                    }
+                    None => next.push(branch),
+                }
+            }
+            branches = next;
+            if !split {
+                break;
+            }
+        }
+        branches
+    }
+
+    // Runs `work` on a search of every branch, on `self.threads` threads. `work` gets the
+    // index of the branch, and returns its result and the stats of the search.
+    fn run<T: Send>(
+        &mut self,
+        board: &Board,
+        work: impl Fn(usize, Search) -> (Option<T>, SolveStats) + Sync,
+    ) -> Vec<Option<T>> {
+        let branches = self.branches(board);
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new((0..branches.len()).map(|_| None).collect::<Vec<_>>());
+        let stats = Mutex::new(SolveStats::default());
+        let options = self.options;
+        let worker = || loop {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            let Some(branch) = branches.get(i) else {
+                break;
+            };
+            let (result, branch_stats) = work(i, Search::new(branch.clone(), options));
+            results.lock().unwrap()[i] = result;
+            *stats.lock().unwrap() += branch_stats;
+        };
+        // Also the only way on targets without threads, see `with_threads`
+        if self.threads == 1 {
+            worker();
+        } else {
+            thread::scope(|scope| {
+                for _ in 0..self.threads.min(branches.len()) {
+                    scope.spawn(worker);
+                }
+            });
+        }
+        self.stats += stats.into_inner().unwrap();
+        results.into_inner().unwrap()
+    }
+}
+
+impl Solver for Parallel {
+    fn solve(&mut self, board: &Board) -> Result<Board, Unsolvable> {
+        // Branches after the first one with a solution need not be searched
+        let first = AtomicUsize::new(usize::MAX);
+        let results = self.run(board, |i, mut search| {
+            let mut solution = None;
+            while first.load(Ordering::Relaxed) > i {
+                match search.step() {
+                    Step::Solution => {
+                        solution = Some(search.board().clone());
+                        first.fetch_min(i, Ordering::Relaxed);
+                        break;
+                    }
+                    Step::Exhausted => break,
+                    _ => {}
+                }
+            }
+            (solution, search.stats())
+        });
+        let mut solution = results.into_iter().flatten().next().ok_or(Unsolvable)?;
+        solution.reveal_counts();
+        Ok(solution)
+    }
+
+    fn enumerate(&mut self, board: &Board, visit: &mut dyn FnMut(&Board) -> bool) {
+        let branches = self.branches(board);
+        let options = self.options;
+        if self.threads == 1 {
+            for branch in branches {
+                let mut search = Search::new(branch, options);
+                let mut stopped = false;
+                while let Some(solution) = search.next_solution() {
+                    if !visit(solution) {
+                        stopped = true;
+                        break;
+                    }
+                }
+                self.stats += search.stats();
+                if stopped {
+                    break;
+                }
+            }
+            return;
+        }
+
+        // Each branch sends its solutions down its own channel, and the channels are read
+        // in branch order. A branch ahead of the one being read waits once its channel is
+        // full, and gives up once the channel is dropped.
+        let (senders, receivers): (Vec<_>, Vec<_>) = branches
+            .iter()
+            .map(|_| mpsc::sync_channel::<Board>(BUFFERED_SOLUTIONS))
+            .unzip();
+        let queue = Mutex::new(branches.into_iter().zip(senders));
+        let stop = AtomicBool::new(false);
+        let stats = Mutex::new(SolveStats::default());
+        let worker = || loop {
+            let Some((branch, sender)) = queue.lock().unwrap().next() else {
+                break;
+            };
+            let mut search = Search::new(branch, options);
+            while !stop.load(Ordering::Relaxed) {
+                match search.step() {
+                    Step::Solution if sender.send(search.board().clone()).is_err() => break,
+                    Step::Exhausted => break,
+                    _ => {}
+                }
+            }
+            *stats.lock().unwrap() += search.stats();
+        };
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(receivers.len()) {
+                scope.spawn(worker);
+            }
+            'branches: for receiver in receivers {
+                for solution in receiver {
+                    if !visit(&solution) {
+                        stop.store(true, Ordering::Relaxed);
+                        break 'branches;
+                    }
+                }
+            }
+        });
+        self.stats += stats.into_inner().unwrap();
+    }
+
+    fn count(&mut self, board: &Board, limit: u64) -> u64 {
+        // Solutions go into the shared total as they are found, so that every branch stops
+        // once the limit is reached between them
+        let total = AtomicU64::new(0);
+        self.run(board, |_, mut search| {
+            while total.load(Ordering::Relaxed) < limit {
+                match search.step() {
+                    Step::Solution => {
+                        total.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Step::Counted(n) => {
+                        total.fetch_add(n, Ordering::Relaxed);
+                    }
+                    Step::Exhausted => break,
+                    Step::Decision { .. } | Step::DeadEnd => {}
+                }
+            }
+            (Some(()), search.stats())
+        });
+        total.into_inner().min(limit)
+    }
+
+    fn stats(&self) -> SolveStats {
+        self.stats
+    }
+}
@@ -0,0 +1,181 @@
+//! Rotations and reflections of boards, and canonical forms for finding duplicate puzzles.
+//!
+//! Transforming a board moves the cells and the counts along with them; when rows and
+//! columns are swapped, so are `row_counts` and `column_counts`.
+
+use std::collections::HashMap;
+
+use crate::{Board, Cell, BOARD_SIZE};
+
+/// One of the 8 symmetries of the square.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    /// Quarter turn clockwise.
+    Rotate90,
+    Rotate180,
+    /// Quarter turn counter-clockwise.
+    Rotate270,
+    /// Mirror left to right.
+    FlipHorizontal,
+    /// Mirror top to bottom.
+    FlipVertical,
+    /// Mirror along the main diagonal.
+    Transpose,
+    /// Mirror along the other diagonal.
+    AntiTranspose,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    /// Where the cell at (r, c) ends up.
+    pub fn apply(self, r: usize, c: usize) -> (usize, usize) {
+        let n = BOARD_SIZE - 1;
+        match self {
+            Transform::Identity => (r, c),
+            Transform::Rotate90 => (c, n - r),
+            Transform::Rotate180 => (n - r, n - c),
+            Transform::Rotate270 => (n - c, r),
+            Transform::FlipHorizontal => (r, n - c),
+            Transform::FlipVertical => (n - r, c),
+            Transform::Transpose => (c, r),
+            Transform::AntiTranspose => (n - c, n - r),
+        }
+    }
+
+    pub fn inverse(self) -> Transform {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            t => t,
+        }
+    }
+
+    /// Whether rows become columns and vice versa.
+    pub fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Transform::Rotate90
+                | Transform::Rotate270
+                | Transform::Transpose
+                | Transform::AntiTranspose
+        )
+    }
+}
+
+impl Board {
+    pub fn transformed(&self, t: Transform) -> Board {
+        let mut board = self.clone();
+        for r in 0..BOARD_SIZE {
+            for c in 0..BOARD_SIZE {
+                let (tr, tc) = t.apply(r, c);
+                board.cells[tr][tc] = self.cells[r][c];
+                board.givens[tr][tc] = self.givens[r][c];
+            }
+        }
+        for i in 0..BOARD_SIZE {
+            // Row `i` goes where its first cell goes, and likewise for column `i`
+            let (row_r, row_c) = t.apply(i, 0);
+            let (col_r, col_c) = t.apply(0, i);
+            if t.swaps_axes() {
+                board.column_counts[row_c] = self.row_counts[i];
+                board.row_counts[col_r] = self.column_counts[i];
+            } else {
+                board.row_counts[row_r] = self.row_counts[i];
+                board.column_counts[col_c] = self.column_counts[i];
+            }
+        }
+        board
+    }
+
+    pub fn rotated(&self) -> Board {
+        self.transformed(Transform::Rotate90)
+    }
+
+    pub fn flipped_horizontally(&self) -> Board {
+        self.transformed(Transform::FlipHorizontal)
+    }
+
+    pub fn flipped_vertically(&self) -> Board {
+        self.transformed(Transform::FlipVertical)
+    }
+
+    pub fn transposed(&self) -> Board {
+        self.transformed(Transform::Transpose)
+    }
+
+    /// The representative of the board's symmetry class, and the transform that leads to it.
+    ///
+    /// All rotations and reflections of a board have the same canonical form.
+    pub fn canonical(&self) -> (Board, Transform) {
+        Transform::ALL
+            .into_iter()
+            .map(|t| (self.transformed(t), t))
+            .min_by_key(|(board, _)| board.canonical_key())
+            .unwrap()
+    }
+
+    /// A hash of the canonical form, stable across runs and versions.
+    pub fn canonical_hash(&self) -> u64 {
+        // FNV-1a
+        self.canonical()
+            .0
+            .canonical_key()
+            .into_iter()
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
+    // Everything that makes up a puzzle, as bytes to compare boards by. Placed cells are
+    // not part of the puzzle.
+    fn canonical_key(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(2 * BOARD_SIZE + BOARD_SIZE * BOARD_SIZE);
+        let counts = self.column_counts.iter().chain(&self.row_counts);
+        key.extend(counts.map(|count| count.unwrap_or(u8::MAX)));
+        let cells = self
+            .cells
+            .iter()
+            .flatten()
+            .zip(self.givens.iter().flatten());
+        key.extend(cells.map(|(cell, given)| match cell {
+            _ if !given => 0,
+            Cell::Unknown => 0,
+            Cell::Empty => 1,
+            Cell::Wall => 2,
+            Cell::Monster => 3,
+            Cell::Chest => 4,
+        }));
+        key
+    }
+}
+
+/// Groups of indices into `boards` that are the same puzzle up to symmetry.
+///
+/// Only groups with more than one board are returned, each sorted, in order of their
+/// first board.
+pub fn find_duplicates(boards: &[Board]) -> Vec<Vec<usize>> {
+    let mut groups = HashMap::<Vec<u8>, Vec<usize>>::new();
+    for (i, board) in boards.iter().enumerate() {
+        groups
+            .entry(board.canonical().0.canonical_key())
+            .or_default()
+            .push(i);
+    }
+    let mut duplicates = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect::<Vec<_>>();
+    duplicates.sort();
+    duplicates
+}
@@ -0,0 +1,283 @@
+//! Every rule violation of a board, together with the cells involved.
+//!
+//! Unlike [`Board::check_solved`], which stops at the first problem, this reports all of
+//! them so that a front-end can highlight the offending cells.
+
+use crate::{
+    room_inside_coords, treasure_room_candidates, Board, BoardError, Cell, Index, BOARD_SIZE,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub error: BoardError,
+    pub cells: Vec<(Index, Index)>,
+}
+
+impl Board {
+    /// All the reasons why the board is not a valid solution; empty iff `check_solved` is `Ok`.
+    ///
+    /// A board with `Unknown` cells only reports a single `Unsolved` violation listing them.
+    pub fn violations(&self) -> Vec<Violation> {
+        use BoardError as E;
+
+        let all_cells =
+            || (0..BOARD_SIZE as Index).flat_map(|r| (0..BOARD_SIZE as Index).map(move |c| (r, c)));
+
+        let unknown = all_cells()
+            .filter(|&(r, c)| matches!(self.at(r, c), Cell::Unknown))
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return vec![Violation {
+                error: E::Unsolved,
+                cells: unknown,
+            }];
+        }
+
+        let mut violations = Vec::new();
+
+        for i in 0..BOARD_SIZE as Index {
+            let walls = (0..BOARD_SIZE as Index)
+                .filter(|&j| matches!(self.at(i, j), Cell::Wall))
+                .count();
+            if self.row_counts[i as usize].is_some_and(|n| n as usize != walls) {
+                violations.push(Violation {
+                    error: E::WrongRowCount(i),
+                    cells: (0..BOARD_SIZE as Index).map(|j| (i, j)).collect(),
+                });
+            }
+        }
+        for j in 0..BOARD_SIZE as Index {
+            let walls = (0..BOARD_SIZE as Index)
+                .filter(|&i| matches!(self.at(i, j), Cell::Wall))
+                .count();
+            if self.column_counts[j as usize].is_some_and(|n| n as usize != walls) {
+                violations.push(Violation {
+                    error: E::WrongColumnCount(j),
+                    cells: (0..BOARD_SIZE as Index).map(|i| (i, j)).collect(),
+                });
+            }
+        }
+
+        let mut treasure_rooms = Vec::new();
+        for (r, c) in all_cells() {
+            let is_monster = matches!(self.at(r, c), Cell::Monster);
+            if is_monster != self.is_dead_end(r, c) {
+                let mut cells = vec![(r, c)];
+                cells.extend(
+                    [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)]
+                        .into_iter()
+                        .filter(|&(r, c)| self.is_in_bounds(r, c)),
+                );
+                violations.push(Violation {
+                    error: if is_monster {
+                        E::MonsterNotInDeadEnd(r, c)
+                    } else {
+                        E::DeadEndWithNoMontster(r, c)
+                    },
+                    cells,
+                });
+            }
+
+            if let Cell::Chest = self.at(r, c) {
+                let candidates = treasure_room_candidates(r, c);
+                match candidates
+                    .into_iter()
+                    .find(|&(r, c)| self.is_treasure_room(r, c))
+                {
+                    Some(room) => treasure_rooms.push(room),
+                    None => {
+                        // The candidate room closest to being one: inside the board, with
+                        // the fewest walls, the first in `treasure_room_candidates` on ties
+                        let (top, left) = candidates
+                            .into_iter()
+                            .filter(|&(r, c)| {
+                                self.is_in_bounds(r, c) && self.is_in_bounds(r + 2, c + 2)
+                            })
+                            .min_by_key(|&(r, c)| {
+                                room_inside_coords(r, c)
+                                    .into_iter()
+                                    .filter(|&(r, c)| matches!(self.at(r, c), Cell::Wall))
+                                    .count()
+                            })
+                            .expect("every cell is in some 3x3 area of the board");
+                        let cells = room_inside_coords(top, left).to_vec();
+                        violations.push(Violation {
+                            error: E::NoTreasureRoomForChest(r, c),
+                            cells,
+                        });
+                    }
+                }
+            }
+        }
+
+        let coords_to_check = self.corridor_width_checks(&treasure_rooms);
+        for i in 0..BOARD_SIZE as Index - 1 {
+            for j in 0..BOARD_SIZE as Index - 1 {
+                if !coords_to_check[i as usize][j as usize] {
+                    continue;
+                }
+                let block = vec![(i, j), (i, j + 1), (i + 1, j), (i + 1, j + 1)];
+                if block
+                    .iter()
+                    .all(|&(r, c)| matches!(self.at(r, c), Cell::Empty))
+                {
+                    violations.push(Violation {
+                        error: E::CorridorsTooWide(i, j),
+                        cells: block,
+                    });
+                }
+            }
+        }
+
+        // Every floor component apart from the largest one is cut off from the main corridor
+        let mut components = self.floor_components();
+        if let Some(main) =
+            (0..components.len()).max_by_key(|&k| (components[k].len(), usize::MAX - k))
+        {
+            components.remove(main);
+        }
+        violations.extend(components.into_iter().map(|cells| Violation {
+            error: E::UnconnectedCorridors,
+            cells,
+        }));
+
+        violations
+    }
+
+    /// Groups of orthogonally connected non-wall cells, in reading order of their first cell.
+    pub fn floor_components(&self) -> Vec<Vec<(Index, Index)>> {
+        let mut seen = [[false; BOARD_SIZE]; BOARD_SIZE];
+        let mut components = Vec::new();
+        for i in 0..BOARD_SIZE as Index {
+            for j in 0..BOARD_SIZE as Index {
+                if seen[i as usize][j as usize] || matches!(self.at(i, j), Cell::Wall) {
+                    continue;
+                }
+                let mut component = Vec::new();
+                let mut to_check = vec![(i, j)];
+                seen[i as usize][j as usize] = true;
+                while let Some((r, c)) = to_check.pop() {
+                    component.push((r, c));
+                    for (r, c) in [(r - 1, c), (r, c - 1), (r, c + 1), (r + 1, c)] {
+                        if matches!(self.at(r, c), Cell::Wall) || seen[r as usize][c as usize] {
+                            continue;
+                        }
+                        seen[r as usize][c as usize] = true;
+                        to_check.push((r, c));
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = " 35344253\n4M   M M \n4        \n2M       \n4       M\n\
+                          6M       \n2       M\n3        \n4 M   M M";
+
+    // Last Call BBS 5-8, solved
+    fn solution() -> Board {
+        let mut board = Board::from_string(PUZZLE).unwrap();
+        board.solve().unwrap();
+        board
+    }
+
+    // The solution with some cells changed, and the violations that makes.
+    fn violations_with(changes: &[((Index, Index), Cell)]) -> Vec<Violation> {
+        let mut board = solution();
+        for &((r, c), cell) in changes {
+            board.cells[r as usize][c as usize] = cell;
+        }
+        board.violations()
+    }
+
+    fn violation(error: BoardError, cells: &[(Index, Index)]) -> Violation {
+        Violation {
+            error,
+            cells: cells.to_vec(),
+        }
+    }
+
+    #[test]
+    fn solutions_have_no_violations() {
+        assert_eq!(solution().violations(), []);
+    }
+
+    #[test]
+    fn unsolved_boards_list_their_unknown_cells() {
+        let violations = Board::from_string(PUZZLE).unwrap().violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].error, BoardError::Unsolved);
+        assert_eq!(violations[0].cells.len(), 64 - 10);
+        assert!(!violations[0].cells.contains(&(0, 0)));
+    }
+
+    #[test]
+    fn wrong_counts_cover_their_line() {
+        let mut board = solution();
+        board.row_counts[2] = Some(3);
+        board.column_counts[7] = Some(4);
+        let row = (0..8).map(|j| (2, j)).collect::<Vec<_>>();
+        let column = (0..8).map(|i| (i, 7)).collect::<Vec<_>>();
+        assert_eq!(
+            board.violations(),
+            [
+                violation(BoardError::WrongRowCount(2), &row),
+                violation(BoardError::WrongColumnCount(7), &column),
+            ]
+        );
+    }
+
+    #[test]
+    fn monsters_must_be_in_dead_ends() {
+        let violations = violations_with(&[((0, 1), Cell::Empty)]);
+        assert!(violations.contains(&violation(
+            BoardError::MonsterNotInDeadEnd(0, 0),
+            &[(0, 0), (0, 1), (1, 0)]
+        )));
+    }
+
+    #[test]
+    fn dead_ends_must_have_monsters() {
+        let violations = violations_with(&[((0, 0), Cell::Empty)]);
+        assert!(violations.contains(&violation(
+            BoardError::DeadEndWithNoMontster(0, 0),
+            &[(0, 0), (0, 1), (1, 0)]
+        )));
+    }
+
+    #[test]
+    fn chests_must_be_in_treasure_rooms() {
+        let violations = violations_with(&[((5, 2), Cell::Chest)]);
+        let room = violations
+            .iter()
+            .find(|v| v.error == BoardError::NoTreasureRoomForChest(5, 2))
+            .expect("the chest has no room");
+        assert_eq!(room.cells.len(), 9);
+        assert!(room.cells.contains(&(5, 2)));
+    }
+
+    #[test]
+    fn corridors_must_be_one_cell_wide() {
+        let violations = violations_with(&[((2, 1), Cell::Empty)]);
+        assert!(violations.contains(&violation(
+            BoardError::CorridorsTooWide(1, 1),
+            &[(1, 1), (1, 2), (2, 1), (2, 2)]
+        )));
+    }
+
+    #[test]
+    fn corridors_must_be_connected() {
+        let violations = violations_with(&[((1, 5), Cell::Wall)]);
+        assert!(violations.contains(&violation(
+            BoardError::UnconnectedCorridors,
+            &[(0, 4), (0, 5), (0, 6)]
+        )));
+    }
+}
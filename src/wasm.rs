@@ -0,0 +1,58 @@
+//! Entry points for the WebAssembly build, wrapped for JavaScript by `wasm/dnd-solver.js`.
+//!
+//! Build with `cargo build --release --lib --target wasm32-unknown-unknown`.
+//!
+//! Strings cross the boundary as UTF-8 in linear memory. JavaScript copies its arguments
+//! into buffers from [`dnd_alloc`] and hands them to [`dnd_call`], which frees them and
+//! returns the length of the JSON result; the result itself is at [`dnd_result_ptr`]
+//! until the next call.
+
+use std::cell::RefCell;
+
+use crate::api::{self, error};
+
+thread_local! {
+    static RESULT: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// A buffer of `len` bytes for an argument of [`dnd_call`].
+#[no_mangle]
+pub extern "C" fn dnd_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+/// Runs an endpoint of [`crate::api`] and returns the length of the result.
+///
+/// # Safety
+///
+/// Both buffers must come from [`dnd_alloc`] with the given lengths; they are freed here.
+#[no_mangle]
+pub unsafe extern "C" fn dnd_call(
+    endpoint: *mut u8,
+    endpoint_len: usize,
+    request: *mut u8,
+    request_len: usize,
+    node_budget: f64,
+) -> usize {
+    let endpoint = Box::from_raw(std::ptr::slice_from_raw_parts_mut(endpoint, endpoint_len));
+    let request = Box::from_raw(std::ptr::slice_from_raw_parts_mut(request, request_len));
+    let result = match (
+        std::str::from_utf8(&endpoint),
+        std::str::from_utf8(&request),
+    ) {
+        (Ok(endpoint), Ok(request)) => {
+            api::call(endpoint, request, node_budget as u64).unwrap_or_else(|e| error(&e.message()))
+        }
+        _ => error("arguments must be UTF-8"),
+    };
+    RESULT.with(|r| {
+        *r.borrow_mut() = result.to_string();
+        r.borrow().len()
+    })
+}
+
+/// The result of the last [`dnd_call`].
+#[no_mangle]
+pub extern "C" fn dnd_result_ptr() -> *const u8 {
+    RESULT.with(|r| r.borrow().as_ptr())
+}
@@ -0,0 +1,161 @@
+//! Zobrist hashing of boards and a bounded transposition table for the search.
+
+use std::sync::LazyLock;
+
+use crate::{rng::SplitMix64, Board, Cell, BOARD_SIZE};
+
+const CELL_VALUES: usize = 5;
+// 0 to BOARD_SIZE walls, or hidden
+const COUNT_VALUES: usize = BOARD_SIZE + 2;
+
+struct Keys {
+    cells: [[[u64; CELL_VALUES]; BOARD_SIZE]; BOARD_SIZE],
+    row_counts: [[u64; COUNT_VALUES]; BOARD_SIZE],
+    column_counts: [[u64; COUNT_VALUES]; BOARD_SIZE],
+}
+
+// Fixed pseudo-random keys, so that hashes are stable between runs
+static KEYS: LazyLock<Keys> = LazyLock::new(|| {
+    let mut rng = SplitMix64(0x5eed_d00d);
+    let mut next = || rng.next_u64();
+    Keys {
+        cells: std::array::from_fn(|_| std::array::from_fn(|_| std::array::from_fn(|_| next()))),
+        row_counts: std::array::from_fn(|_| std::array::from_fn(|_| next())),
+        column_counts: std::array::from_fn(|_| std::array::from_fn(|_| next())),
+    }
+});
+
+fn cell_index(cell: Cell) -> usize {
+    match cell {
+        Cell::Unknown => 0,
+        Cell::Empty => 1,
+        Cell::Wall => 2,
+        Cell::Monster => 3,
+        Cell::Chest => 4,
+    }
+}
+
+fn count_index(count: Option<u8>) -> usize {
+    count.map_or(COUNT_VALUES - 1, |n| (n as usize).min(COUNT_VALUES - 2))
+}
+
+impl Board {
+    /// Zobrist hash of the cells and the counts.
+    pub fn zobrist_hash(&self) -> u64 {
+        let keys = &*KEYS;
+        let mut hash = 0;
+        for (r, row) in self.cells.iter().enumerate() {
+            for (c, &cell) in row.iter().enumerate() {
+                hash ^= keys.cells[r][c][cell_index(cell)];
+            }
+        }
+        for i in 0..BOARD_SIZE {
+            hash ^= keys.row_counts[i][count_index(self.row_counts[i])];
+            hash ^= keys.column_counts[i][count_index(self.column_counts[i])];
+        }
+        hash
+    }
+}
+
+/// Number of solutions of search states, by Zobrist hash.
+///
+/// The table has a fixed number of slots; a new entry replaces whatever was in its slot.
+/// Each entry keeps the cells and counts of its board, so that two boards with the same
+/// hash are never mistaken for one another.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable {
+    slots: Vec<Option<Entry>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    hash: u64,
+    cells: [[Cell; BOARD_SIZE]; BOARD_SIZE],
+    row_counts: [Option<u8>; BOARD_SIZE],
+    column_counts: [Option<u8>; BOARD_SIZE],
+    solutions: u64,
+}
+
+impl TranspositionTable {
+    /// A table with room for at least `capacity` entries.
+    pub fn new(capacity: usize) -> TranspositionTable {
+        TranspositionTable {
+            slots: vec![None; capacity.max(1).next_power_of_two()],
+        }
+    }
+
+    /// The number of solutions recorded for `board`, whose Zobrist hash is `hash`.
+    pub fn get(&self, hash: u64, board: &Board) -> Option<u64> {
+        match &self.slots[self.slot(hash)] {
+            Some(entry)
+                if entry.hash == hash
+                    && entry.cells == board.cells
+                    && entry.row_counts == board.row_counts
+                    && entry.column_counts == board.column_counts =>
+            {
+                Some(entry.solutions)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, hash: u64, board: &Board, solutions: u64) {
+        let slot = self.slot(hash);
+        self.slots[slot] = Some(Entry {
+            hash,
+            cells: board.cells,
+            row_counts: board.row_counts,
+            column_counts: board.column_counts,
+            solutions,
+        });
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        hash as usize & (self.slots.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> Board {
+        Board::from_string(
+            " 35344253\n4M   M M \n4        \n2M       \n4       M\n\
+             6M       \n2       M\n3        \n4 M   M M",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn hashes_follow_cells_and_counts() {
+        let board = board();
+        let mut wall = board.clone();
+        wall.cells[1][1] = Cell::Wall;
+        let mut hidden = board.clone();
+        hidden.row_counts[0] = None;
+        assert_eq!(board.zobrist_hash(), board.clone().zobrist_hash());
+        assert_ne!(board.zobrist_hash(), wall.zobrist_hash());
+        assert_ne!(board.zobrist_hash(), hidden.zobrist_hash());
+    }
+
+    #[test]
+    fn entries_are_found_only_for_their_own_board() {
+        let board = board();
+        let mut other = board.clone();
+        other.cells[1][1] = Cell::Wall;
+        let hash = board.zobrist_hash();
+
+        let mut table = TranspositionTable::new(16);
+        assert_eq!(table.get(hash, &board), None);
+        table.insert(hash, &board, 3);
+        assert_eq!(table.get(hash, &board), Some(3));
+        // A collision: same hash, different board
+        assert_eq!(table.get(hash, &other), None);
+
+        // A later entry for the same slot replaces the earlier one
+        table.insert(hash, &other, 5);
+        assert_eq!(table.get(hash, &board), None);
+        assert_eq!(table.get(hash, &other), Some(5));
+    }
+}
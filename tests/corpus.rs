@@ -0,0 +1,162 @@
+//! Regression tests over the puzzle corpus in `tests/corpus`.
+
+use zach_dnd_solver::{
+    format::{parse_puzzles, Puzzle},
+    search::{SolveOptions, ValueOrder, VariableOrder},
+    solver::SolverKind,
+    BoardError,
+};
+
+const OFFICIAL: &str = include_str!("corpus/official.txt");
+const OFFICIAL_SOLUTIONS: &str = include_str!("corpus/official-solutions.txt");
+const BOARDS: &str = include_str!("corpus/boards.txt");
+const BOARDS_EXPECTED: &str = include_str!("corpus/boards-expected.txt");
+
+fn load(spec: &str) -> Vec<Puzzle> {
+    let puzzles = parse_puzzles(spec).expect("corpus files are valid");
+    assert!(!puzzles.is_empty());
+    puzzles
+}
+
+fn title(puzzle: &Puzzle) -> &str {
+    puzzle
+        .metadata
+        .title
+        .as_deref()
+        .expect("corpus entries have a title")
+}
+
+// Each official puzzle with its known solution.
+fn official() -> Vec<(Puzzle, Puzzle)> {
+    let puzzles = load(OFFICIAL);
+    let solutions = load(OFFICIAL_SOLUTIONS);
+    for solution in &solutions {
+        assert!(
+            puzzles.iter().any(|p| title(p) == title(solution)),
+            "no puzzle for the solution {}",
+            title(solution)
+        );
+    }
+    puzzles
+        .into_iter()
+        .map(|puzzle| {
+            let solution = solutions
+                .iter()
+                .find(|s| title(s) == title(&puzzle))
+                .unwrap_or_else(|| panic!("no solution for {}", title(&puzzle)))
+                .clone();
+            (puzzle, solution)
+        })
+        .collect()
+}
+
+#[test]
+fn official_solutions_are_valid() {
+    for (puzzle, solution) in official() {
+        assert_eq!(solution.board.check_solved(), Ok(()), "{}", title(&puzzle));
+        assert_eq!(
+            solution.board.row_counts,
+            puzzle.board.row_counts,
+            "{}",
+            title(&puzzle)
+        );
+        assert_eq!(
+            solution.board.column_counts,
+            puzzle.board.column_counts,
+            "{}",
+            title(&puzzle)
+        );
+    }
+}
+
+#[test]
+fn official_puzzles_are_not_solved() {
+    for (puzzle, _) in official() {
+        assert_eq!(
+            puzzle.board.check_solved(),
+            Err(BoardError::Unsolved),
+            "{}",
+            title(&puzzle)
+        );
+    }
+}
+
+#[test]
+fn official_puzzles_solve_to_the_known_solution() {
+    for (puzzle, solution) in official() {
+        for variable_order in VariableOrder::ALL {
+            for value_order in ValueOrder::ALL {
+                let options = SolveOptions {
+                    variable_order,
+                    value_order,
+                };
+                let mut board = puzzle.board.clone();
+                board
+                    .solve_with(&options, &mut Default::default())
+                    .unwrap_or_else(|_| panic!("{} is unsolvable", title(&puzzle)));
+                assert_eq!(
+                    board.cells,
+                    solution.board.cells,
+                    "{} with {variable_order:?} and {value_order:?}",
+                    title(&puzzle)
+                );
+            }
+        }
+        for kind in SolverKind::ALL {
+            let solved = kind
+                .solver(SolveOptions::default())
+                .solve(&puzzle.board)
+                .unwrap_or_else(|_| panic!("{} is unsolvable with {kind:?}", title(&puzzle)));
+            assert_eq!(
+                solved.cells,
+                solution.board.cells,
+                "{} with {kind:?}",
+                title(&puzzle)
+            );
+        }
+    }
+}
+
+#[test]
+fn official_puzzles_have_a_unique_solution() {
+    for (puzzle, _) in official() {
+        assert_eq!(puzzle.board.count_solutions(2), 1, "{}", title(&puzzle));
+        for kind in SolverKind::ALL {
+            let mut solver = kind.solver(SolveOptions::default());
+            assert_eq!(
+                solver.count(&puzzle.board, 2),
+                1,
+                "{} with {kind:?}",
+                title(&puzzle)
+            );
+        }
+        let certificate = puzzle
+            .board
+            .certify()
+            .unwrap_or_else(|e| panic!("{}: {e:?}", title(&puzzle)));
+        assert_eq!(certificate.check(), Ok(()), "{}", title(&puzzle));
+    }
+}
+
+#[test]
+fn hand_made_boards_give_the_expected_result() {
+    let expected = BOARDS_EXPECTED
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.split_once(": ").expect("`title: result` lines"))
+        .collect::<Vec<_>>();
+    let boards = load(BOARDS);
+    assert_eq!(boards.len(), expected.len());
+    for puzzle in boards {
+        let expected = expected
+            .iter()
+            .find(|&&(t, _)| t == title(&puzzle))
+            .map(|&(_, result)| result)
+            .unwrap_or_else(|| panic!("{} has no expected result", title(&puzzle)));
+        let actual = match puzzle.board.check_solved() {
+            Ok(()) => "Ok".to_string(),
+            Err(e) => format!("{e:?}"),
+        };
+        assert_eq!(actual, expected, "{}", title(&puzzle));
+    }
+}
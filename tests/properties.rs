@@ -0,0 +1,291 @@
+//! Randomized tests over generated dungeons, and differential tests between the
+//! different ways of solving a puzzle.
+//!
+//! Everything is seeded, so a failure names the seed that reproduces it.
+
+use zach_dnd_solver::{
+    generate::{GenerateOptions, Generated},
+    lines::solve_line,
+    rng::SplitMix64,
+    search::{Search, SolveOptions, ValueOrder, VariableOrder},
+    solver::{Parallel, Solver, SolverKind},
+    symmetry::Transform,
+    Board, Cell, BOARD_SIZE,
+};
+
+const SEEDS: u64 = 24;
+
+// Hiding counts is left out, since minimizing them is slow in unoptimized builds.
+fn generate(seed: u64) -> Generated {
+    Board::generate(&GenerateOptions {
+        seed,
+        rooms: 1 + seed as usize % 2,
+        hide_counts: false,
+    })
+}
+
+// The solution with `count` of its walls and floor cells turned back into `Unknown`.
+fn blanked(solution: &Board, count: usize, rng: &mut SplitMix64) -> Board {
+    let mut board = solution.clone();
+    let mut cells = (0..BOARD_SIZE)
+        .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
+        .filter(|&(r, c)| matches!(solution.cells[r][c], Cell::Wall | Cell::Empty))
+        .collect::<Vec<_>>();
+    for _ in 0..count.min(cells.len()) {
+        let (r, c) = cells.swap_remove(rng.below(cells.len()));
+        board.cells[r][c] = Cell::Unknown;
+        board.givens[r][c] = false;
+    }
+    board
+}
+
+// Counts solutions by trying every assignment of the `Unknown` cells, sharing nothing
+// with the solver but `check_solved`.
+fn brute_force_count(board: &Board) -> u64 {
+    let unknown = (0..BOARD_SIZE)
+        .flat_map(|r| (0..BOARD_SIZE).map(move |c| (r, c)))
+        .filter(|&(r, c)| board.cells[r][c] == Cell::Unknown)
+        .collect::<Vec<_>>();
+    assert!(unknown.len() <= 16, "too many cells to brute force");
+    let mut count = 0;
+    for bits in 0..1u32 << unknown.len() {
+        let mut candidate = board.clone();
+        for (i, &(r, c)) in unknown.iter().enumerate() {
+            candidate.cells[r][c] = if bits >> i & 1 == 1 {
+                Cell::Wall
+            } else {
+                Cell::Empty
+            };
+        }
+        // Hidden counts allow any number of walls, but `check_solved` needs them all
+        candidate.reveal_counts();
+        if (0..BOARD_SIZE).all(|i| {
+            board.row_counts[i].is_none_or(|n| Some(n) == candidate.row_counts[i])
+                && board.column_counts[i].is_none_or(|n| Some(n) == candidate.column_counts[i])
+        }) && candidate.check_solved().is_ok()
+        {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[test]
+fn generated_solutions_are_valid_and_unique() {
+    for seed in 0..SEEDS {
+        let Generated { puzzle, solution } = generate(seed);
+        assert_eq!(solution.check_solved(), Ok(()), "seed {seed}");
+        assert_eq!(puzzle.count_solutions(2), 1, "seed {seed}");
+        for kind in SolverKind::ALL {
+            let solved = kind
+                .solver(SolveOptions::default())
+                .solve(&puzzle)
+                .unwrap_or_else(|_| panic!("seed {seed} is unsolvable with {kind:?}"));
+            assert_eq!(solved.cells, solution.cells, "seed {seed} with {kind:?}");
+        }
+        let mut solved = puzzle.clone();
+        solved
+            .solve()
+            .unwrap_or_else(|_| panic!("seed {seed} is unsolvable"));
+        assert_eq!(solved.cells, solution.cells, "seed {seed}");
+    }
+}
+
+#[test]
+fn blanked_solutions_can_be_solved_again() {
+    let mut rng = SplitMix64(1);
+    for seed in 0..SEEDS {
+        let solution = generate(seed).solution;
+        for count in [8, 24, 64] {
+            let mut board = blanked(&solution, count, &mut rng);
+            board
+                .solve()
+                .unwrap_or_else(|_| panic!("seed {seed} with {count} blanks is unsolvable"));
+            assert_eq!(board.check_solved(), Ok(()), "seed {seed}, {count} blanks");
+        }
+    }
+}
+
+#[test]
+fn transformed_puzzles_have_transformed_solutions() {
+    for seed in 0..SEEDS {
+        let Generated { puzzle, solution } = generate(seed);
+        for t in Transform::ALL {
+            let mut solved = puzzle.transformed(t);
+            solved
+                .solve()
+                .unwrap_or_else(|_| panic!("seed {seed} under {t:?} is unsolvable"));
+            assert_eq!(
+                solved.cells,
+                solution.transformed(t).cells,
+                "seed {seed} under {t:?}"
+            );
+            assert_eq!(
+                solved.count_solutions(2),
+                1,
+                "seed {seed} under {t:?} is not unique"
+            );
+        }
+    }
+}
+
+#[test]
+fn solver_strategies_agree_on_solution_counts() {
+    let mut rng = SplitMix64(2);
+    for seed in 0..SEEDS {
+        let mut board = blanked(&generate(seed).solution, 12, &mut rng);
+        if seed % 2 == 1 {
+            board.row_counts[rng.below(BOARD_SIZE)] = None;
+            board.column_counts[rng.below(BOARD_SIZE)] = None;
+        }
+        let expected = brute_force_count(&board);
+        assert!(
+            expected >= 1,
+            "seed {seed}: the solution itself was not counted"
+        );
+
+        for variable_order in VariableOrder::ALL {
+            for value_order in ValueOrder::ALL {
+                let options = SolveOptions {
+                    variable_order,
+                    value_order,
+                };
+                assert_eq!(
+                    Search::new(board.clone(), options).count_solutions(u64::MAX),
+                    expected,
+                    "seed {seed} with {options:?}"
+                );
+            }
+        }
+        for kind in SolverKind::ALL {
+            let mut solver = kind.solver(SolveOptions::default());
+            assert_eq!(
+                solver.count(&board, u64::MAX),
+                expected,
+                "seed {seed} with {kind:?}"
+            );
+            let mut enumerated = 0;
+            solver.enumerate(&board, &mut |solution| {
+                assert_eq!(solution.check_solved(), Ok(()), "seed {seed} with {kind:?}");
+                enumerated += 1;
+                true
+            });
+            assert_eq!(enumerated, expected, "seed {seed} with {kind:?} enumerated");
+        }
+        let enumerated = Search::new(board.clone(), SolveOptions::default())
+            .inspect(|solution| assert_eq!(solution.check_solved(), Ok(()), "seed {seed}"))
+            .count();
+        assert_eq!(enumerated as u64, expected, "seed {seed} enumerated");
+        assert_eq!(
+            board.certify().is_ok(),
+            expected == 1,
+            "seed {seed} certified"
+        );
+    }
+}
+
+#[test]
+fn parallel_enumeration_keeps_the_search_order_and_stops_early() {
+    // Last Call BBS 5-8 with its counts hidden, which has thousands of solutions
+    let board = Board::from_string(
+        " ????????\n?M   M M \n?        \n?M       \n?       M\n\
+         ?M       \n?       M\n?        \n? M   M M",
+    )
+    .unwrap();
+    let expected = Search::new(board.clone(), SolveOptions::default())
+        .take(100)
+        .collect::<Vec<_>>();
+
+    for threads in [1, 4] {
+        let mut solver = Parallel::with_threads(SolveOptions::default(), threads);
+        let mut visited = Vec::new();
+        solver.enumerate(&board, &mut |solution| {
+            visited.push(solution.clone());
+            visited.len() < expected.len()
+        });
+        assert_eq!(visited, expected, "{threads} threads");
+    }
+}
+
+#[test]
+fn parallel_counting_stops_at_the_limit() {
+    // Last Call BBS 5-8 with its counts hidden, which has thousands of solutions
+    let board = Board::from_string(
+        " ????????\n?M   M M \n?        \n?M       \n?       M\n\
+         ?M       \n?       M\n?        \n? M   M M",
+    )
+    .unwrap();
+    for threads in [1, 4] {
+        let mut solver = Parallel::with_threads(SolveOptions::default(), threads);
+        assert_eq!(solver.count(&board, 50), 50, "{threads} threads");
+        // Each thread finds at most one more solution after the limit is reached
+        let found = solver.stats().solutions;
+        assert!(
+            found <= 50 + threads as u64,
+            "{threads} threads found {found}"
+        );
+    }
+}
+
+#[test]
+fn from_string_round_trips_generated_boards() {
+    for seed in 0..SEEDS {
+        let Generated { puzzle, solution } = generate(seed);
+        for board in [puzzle, solution] {
+            let text = board.to_string();
+            assert_eq!(Board::from_string(&text).ok(), Some(board), "{text}");
+        }
+    }
+}
+
+#[test]
+fn from_string_survives_mutated_input() {
+    // A cheap stand-in for the fuzz target in `fuzz/`, run with every `cargo test`
+    let mut rng = SplitMix64(3);
+    let alphabet = b" .,#xMC?0123456789\n\r\t-";
+    for seed in 0..SEEDS {
+        let mut bytes = generate(seed).puzzle.to_string().into_bytes();
+        for _ in 0..200 {
+            let i = rng.below(bytes.len());
+            match rng.below(3) {
+                0 => bytes[i] = alphabet[rng.below(alphabet.len())],
+                1 => bytes.insert(i, alphabet[rng.below(alphabet.len())]),
+                _ if bytes.len() > 1 => {
+                    bytes.remove(i);
+                }
+                _ => {}
+            }
+            let text = String::from_utf8_lossy(&bytes);
+            if let Ok(board) = Board::from_string(&text) {
+                assert_eq!(
+                    Board::from_string(&board.to_string()).ok(),
+                    Some(board),
+                    "{text:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn counts_above_the_board_size_are_rejected() {
+    let puzzle = generate(0).puzzle;
+    let text = puzzle.to_string();
+    // The first count of the first line is the first column's
+    let first = text.find(|ch: char| ch.is_ascii_digit()).unwrap();
+    let mut mutated = text.clone().into_bytes();
+    mutated[first] = b'9';
+    assert!(Board::from_string(&String::from_utf8(mutated).unwrap()).is_err());
+
+    assert_eq!(solve_line(&[Cell::Unknown; BOARD_SIZE], Some(9)), None);
+    let mut board = puzzle.clone();
+    board.row_counts[0] = Some(BOARD_SIZE as u8 + 1);
+    assert!(board.clone().solve().is_err());
+    assert_eq!(board.count_solutions(2), 0);
+    for kind in SolverKind::ALL {
+        assert!(
+            kind.solver(SolveOptions::default()).solve(&board).is_err(),
+            "{kind:?}"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Requests to a real [`Server`] over a local socket.
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use zach_dnd_solver::{
+    format::parse_puzzles,
+    json::Json,
+    server::{Server, ServerOptions},
+    Board,
+};
+
+const MAX_REQUEST_BYTES: usize = 1024;
+
+// An official puzzle, which takes a few dozen search nodes to solve.
+fn puzzle() -> Board {
+    let corpus = include_str!("corpus/official.txt");
+    parse_puzzles(corpus).unwrap().swap_remove(0).board
+}
+
+// Serves on a free port for the rest of the test run.
+fn serve(node_budget: u64) -> SocketAddr {
+    serve_with(ServerOptions {
+        max_request_bytes: MAX_REQUEST_BYTES,
+        node_budget,
+        ..ServerOptions::default()
+    })
+}
+
+fn serve_with(options: ServerOptions) -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0", options).unwrap();
+    let addr = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    addr
+}
+
+// Sends `request` as is, and returns the status and body of the response.
+fn send(addr: SocketAddr, request: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, body.to_string())
+}
+
+fn post(addr: SocketAddr, endpoint: &str, body: &str) -> (u16, String) {
+    send(
+        addr,
+        &format!(
+            "POST /{endpoint} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        ),
+    )
+}
+
+// The `error` message of a response body.
+fn error_message(body: &str) -> String {
+    let body = Json::parse(body).unwrap();
+    body.get("error")
+        .and_then(Json::as_str)
+        .unwrap()
+        .to_string()
+}
+
+fn puzzle_json(board: &Board) -> Json {
+    Json::String(format!("puzzles 1\n{board}"))
+}
+
+fn puzzle_request(board: &Board) -> String {
+    Json::Object(vec![("puzzle".to_string(), puzzle_json(board))]).to_string()
+}
+
+#[test]
+fn answers_requests() {
+    let addr = serve(ServerOptions::default().node_budget);
+    let (status, body) = post(addr, "parse", &puzzle_request(&puzzle()));
+    assert_eq!(status, 200, "{body}");
+    let (status, body) = post(addr, "solve", &puzzle_request(&puzzle()));
+    assert_eq!(status, 200, "{body}");
+    assert!(Json::parse(&body).unwrap().get("solution").is_some());
+    let (status, _) = post(addr, "nowhere", "{}");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn rejects_requests_over_the_size_limit() {
+    let addr = serve(ServerOptions::default().node_budget);
+    let padding = " ".repeat(MAX_REQUEST_BYTES);
+    let (status, _) = post(
+        addr,
+        "parse",
+        &format!("{}{padding}", puzzle_request(&puzzle())),
+    );
+    assert_eq!(status, 413);
+
+    // A length that overflows when added to the size of the headers
+    let (status, _) = send(
+        addr,
+        &format!(
+            "POST /parse HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            u64::MAX
+        ),
+    );
+    assert_eq!(status, 413);
+    let (status, _) = send(addr, &format!("POST /parse HTTP/1.1\r\n{padding}\r\n\r\n"));
+    assert_eq!(status, 413);
+
+    // The server is still up
+    let (status, _) = post(addr, "parse", &puzzle_request(&puzzle()));
+    assert_eq!(status, 200);
+}
+
+#[test]
+fn gives_up_once_the_node_budget_is_spent() {
+    let addr = serve(10);
+    let count = format!(
+        "{{\"puzzle\": {}, \"limit\": 1000000}}",
+        puzzle_json(&puzzle())
+    );
+    let requests = [
+        ("solve", puzzle_request(&puzzle())),
+        ("count", count),
+        ("hint", puzzle_request(&puzzle())),
+        (
+            "generate",
+            "{\"seed\": 1, \"hide_counts\": true}".to_string(),
+        ),
+    ];
+    for (endpoint, request) in requests {
+        let (status, body) = post(addr, endpoint, &request);
+        assert_eq!(status, 422, "{endpoint}: {body}");
+        let message = Json::parse(&body).unwrap();
+        assert_eq!(
+            message.get("error").and_then(Json::as_str),
+            Some("gave up after 10 search nodes"),
+            "{endpoint}"
+        );
+    }
+}
+
+#[test]
+fn explains_errors_in_the_body() {
+    let addr = serve(ServerOptions::default().node_budget);
+    let (status, body) = post(addr, "parse", "{");
+    assert_eq!(
+        (status, error_message(&body).as_str()),
+        (400, "invalid JSON")
+    );
+    let (status, body) = post(addr, "parse", "[]");
+    assert_eq!(
+        (status, error_message(&body).as_str()),
+        (400, "expected a JSON object")
+    );
+    let (status, body) = post(addr, "nowhere", "{}");
+    assert_eq!(
+        (status, error_message(&body).as_str()),
+        (404, "no endpoint `nowhere`")
+    );
+    let (status, body) = send(addr, "GET /parse HTTP/1.1\r\n\r\n");
+    assert_eq!(
+        (status, error_message(&body).as_str()),
+        (405, "only POST is supported")
+    );
+    let (status, body) = send(addr, "POST /parse HTTP/1.1\r\n\r\n");
+    assert_eq!(
+        (status, error_message(&body).as_str()),
+        (411, "Content-Length is required")
+    );
+    let (status, body) = post(addr, "generate", "{\"rooms\": 3}");
+    assert_eq!(
+        (status, error_message(&body).as_str()),
+        (400, "at most 2 rooms are supported")
+    );
+}
+
+#[test]
+fn answers_hints() {
+    let addr = serve(ServerOptions::default().node_budget);
+    let (status, body) = post(addr, "hint", &puzzle_request(&puzzle()));
+    assert_eq!(status, 200, "{body}");
+    let body = Json::parse(&body).unwrap();
+    let hint = body.get("hint").unwrap();
+    assert_eq!(hint.get("kind").and_then(Json::as_str), Some("deduction"));
+    assert!(hint.get("row").and_then(Json::as_u64).is_some());
+    assert!(hint.get("value").and_then(Json::as_str).is_some());
+    assert!(hint.get("reason").and_then(Json::as_str).is_some());
+
+    let mut solution = puzzle();
+    solution.solve().unwrap();
+    let (status, body) = post(addr, "hint", &puzzle_request(&solution));
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(Json::parse(&body).unwrap().get("hint"), Some(&Json::Null));
+}
+
+#[test]
+fn generates_puzzles_with_their_solution() {
+    let addr = serve(ServerOptions::default().node_budget);
+    let (status, body) = post(addr, "generate", "{\"seed\": 1}");
+    assert_eq!(status, 200, "{body}");
+    let body = Json::parse(&body).unwrap();
+    let board = |key: &str| {
+        let text = body
+            .get(key)
+            .and_then(|b| b.get("text"))
+            .and_then(Json::as_str);
+        Board::from_string(text.unwrap()).unwrap()
+    };
+    let (puzzle, solution) = (board("puzzle"), board("solution"));
+    assert!(solution.check_solved().is_ok());
+    assert_eq!(puzzle.count_solutions(2), 1);
+    let mut solved = puzzle.clone();
+    solved.solve().unwrap();
+    assert_eq!(solved.cells, solution.cells);
+}
+
+#[test]
+fn gives_up_on_clients_that_send_too_slowly() {
+    let timeout = Duration::from_millis(300);
+    let addr = serve_with(ServerOptions {
+        request_timeout: timeout,
+        ..ServerOptions::default()
+    });
+    // A client that keeps sending, one byte every 100ms, until it gets an answer
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    let start = Instant::now();
+    let mut response = Vec::new();
+    for &byte in b"POST /parse HTTP/1.1\r\nContent-Length: 2\r\n\r\n" {
+        stream.write_all(&[byte]).unwrap();
+        let mut chunk = [0; 1024];
+        if let Ok(n) = stream.read(&mut chunk) {
+            response.extend_from_slice(&chunk[..n]);
+            break;
+        }
+    }
+    stream.set_read_timeout(None).unwrap();
+    stream.read_to_end(&mut response).unwrap();
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 408"), "{response:?}");
+    assert!(start.elapsed() < 3 * timeout);
+
+    // The next client is answered
+    let (status, _) = post(addr, "parse", &puzzle_request(&puzzle()));
+    assert_eq!(status, 200);
+}
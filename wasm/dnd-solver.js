@@ -0,0 +1,76 @@
+// JavaScript bindings for the WebAssembly build of the solver (see `src/wasm.rs`).
+//
+//     import * as dnd from "./dnd-solver.js";
+//     await dnd.init(fetch("zach_dnd_solver.wasm"));
+//     const { solution } = dnd.solve(text);
+//
+// Every function returns a plain object shaped like the results in `src/api.rs`, and
+// throws an `Error` with the message from the solver if the call fails.
+
+let exports = null;
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+const DEFAULT_NODE_BUDGET = 1_000_000;
+
+// Accepts anything `WebAssembly.instantiateStreaming` or `instantiate` does: a `Response`
+// (or a promise of one), an `ArrayBuffer` or a typed array.
+export async function init(source) {
+  source = await source;
+  const { instance } =
+    typeof Response !== "undefined" && source instanceof Response
+      ? await WebAssembly.instantiateStreaming(source)
+      : await WebAssembly.instantiate(source);
+  exports = instance.exports;
+}
+
+function pass(string) {
+  const bytes = encoder.encode(string);
+  const ptr = exports.dnd_alloc(bytes.length);
+  new Uint8Array(exports.memory.buffer, ptr, bytes.length).set(bytes);
+  return [ptr, bytes.length];
+}
+
+function call(endpoint, request, nodeBudget = DEFAULT_NODE_BUDGET) {
+  if (exports === null) {
+    throw new Error("call init() first");
+  }
+  const len = exports.dnd_call(...pass(endpoint), ...pass(JSON.stringify(request)), nodeBudget);
+  const ptr = exports.dnd_result_ptr();
+  const result = JSON.parse(decoder.decode(new Uint8Array(exports.memory.buffer, ptr, len)));
+  if ("error" in result) {
+    throw new Error(result.error);
+  }
+  return result;
+}
+
+// `{ board, metadata }` for a puzzle in the text format, bare grid or with a header.
+export function parse(text) {
+  return call("parse", { puzzle: text });
+}
+
+// `{ solution, nodes }`; throws if there is no solution or the search takes more than
+// `nodeBudget` nodes.
+export function solve(text, { nodeBudget } = {}) {
+  return call("solve", { puzzle: text }, nodeBudget);
+}
+
+// `{ solved, violations }`, with one `{ error, cells }` entry per broken rule.
+export function check(text) {
+  return call("validate", { puzzle: text });
+}
+
+// `{ solutions, finished }`, counting up to `limit` solutions.
+export function count(text, { limit = 2, nodeBudget } = {}) {
+  return call("count", { puzzle: text, limit }, nodeBudget);
+}
+
+// `{ hint }`: `null` for a solved board, a `deduction` or a `conflict`.
+export function hint(text) {
+  return call("hint", { puzzle: text });
+}
+
+// `{ puzzle, solution }`; the same seed always gives the same puzzle.
+export function generate(seed, { rooms = 1, hideCounts = false } = {}) {
+  return call("generate", { seed, rooms, hide_counts: hideCounts });
+}